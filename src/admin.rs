@@ -0,0 +1,411 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{
+        ChatAdministratorRights, ChatMember, ChatMemberStatus, ChatMembersFilter, ChatPermissions, ChatType, Error,
+        MessageSender, SupergroupMembersFilter, TgEventData,
+    },
+    manager::Manager,
+    scheduler::Priority,
+};
+use crate::view::{self, PrettyPrint};
+use colored::Colorize;
+use futures::StreamExt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `rsm admin <subcommand> <chat_id> ...`, group and channel administration. Telegram refusing, say for missing
+/// rights, is reported like any other error.
+pub async fn run(manager: &Manager, args: &Args) {
+    let result = match args.positional(0) {
+        Some("info") => info(manager, args).await,
+        Some("members") => members(manager, args).await,
+        Some("add") => manager.add_chat_member(args.chat(manager, 1).await, args.id(2, "user id")).await,
+        Some("ban") => {
+            manager
+                .ban_chat_member(args.chat(manager, 1).await, args.id(2, "user id"), until(args), args.flag("revoke"))
+                .await
+        }
+        Some("unban") => manager.unban_chat_member(args.chat(manager, 1).await, args.id(2, "user id")).await,
+        Some("restrict") => {
+            manager
+                .restrict_chat_member(args.chat(manager, 1).await, args.id(2, "user id"), permissions(args), until(args))
+                .await
+        }
+        Some("promote") => {
+            manager
+                .promote_chat_member(
+                    args.chat(manager, 1).await,
+                    args.id(2, "user id"),
+                    rights(args),
+                    args.value("title").unwrap_or_default(),
+                )
+                .await
+        }
+        Some("demote") => manager.demote_chat_member(args.chat(manager, 1).await, args.id(2, "user id")).await,
+        Some("title") => manager.set_chat_title(args.chat(manager, 1).await, &text(args)).await,
+        Some("description") => manager.set_chat_description(args.chat(manager, 1).await, &args.rest(2)).await,
+        Some("photo") => manager.set_chat_photo(args.chat(manager, 1).await, args.positional(2)).await,
+        Some("links") => links(manager, args).await,
+        Some("create-link") => create_link(manager, args).await,
+        Some("revoke-link") => {
+            let link = args
+                .positional(2)
+                .unwrap_or_else(|| cli::exit_with_usage("no invite link given"));
+            manager.revoke_chat_invite_link(args.chat(manager, 1).await, link).await.map(|_| ())
+        }
+        Some("requests") => requests(manager, args).await,
+        Some("approve") => {
+            manager
+                .process_chat_join_request(args.chat(manager, 1).await, args.id(2, "user id"), true)
+                .await
+        }
+        Some("decline") => {
+            manager
+                .process_chat_join_request(args.chat(manager, 1).await, args.id(2, "user id"), false)
+                .await
+        }
+        Some(other) => cli::exit_with_usage(&format!("unknown admin command '{}'", other)),
+        None => cli::exit_with_usage("no admin command given"),
+    };
+
+    result.unwrap_or_else(|error| cli::exit_with_error(error));
+}
+
+fn text(args: &Args) -> String {
+    let text = args.rest(2);
+    if text.is_empty() {
+        cli::exit_with_usage("no text given");
+    }
+    text
+}
+
+/// `--until` as a unixtime, given relative to now like `30m`, `12h` or `7d`. Missing means forever, i.e. 0.
+fn until(args: &Args) -> i32 {
+    let value = match args.value("until") {
+        Some(value) => value,
+        None => return 0,
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Can't get unixtime")
+        .as_secs() as i32;
+
+    cli::duration(value)
+        .and_then(|seconds| now.checked_add(seconds))
+        .unwrap_or_else(|| cli::exit_with_usage("--until expects a duration like 30m, 12h or 7d"))
+}
+
+fn list(args: &Args, name: &str) -> Vec<String> {
+    args.value(name)
+        .map(|value| value.split(',').map(|it| it.trim().to_owned()).collect())
+        .unwrap_or_default()
+}
+
+/// `--allow messages,media,...`, everything not listed is forbidden.
+fn permissions(args: &Args) -> ChatPermissions {
+    let mut permissions = ChatPermissions::default();
+
+    for permission in list(args, "allow") {
+        match permission.as_str() {
+            "messages" => permissions.can_send_messages = true,
+            "media" => permissions.can_send_media_messages = true,
+            "polls" => permissions.can_send_polls = true,
+            "other" => permissions.can_send_other_messages = true,
+            "previews" => permissions.can_add_web_page_previews = true,
+            "info" => permissions.can_change_info = true,
+            "invite" => permissions.can_invite_users = true,
+            "pin" => permissions.can_pin_messages = true,
+            other => cli::exit_with_usage(&format!("unknown permission '{}'", other)),
+        }
+    }
+
+    permissions
+}
+
+/// `--rights delete,restrict,...`, defaults to what a moderator usually needs.
+fn rights(args: &Args) -> ChatAdministratorRights {
+    let mut names = list(args, "rights");
+    if names.is_empty() {
+        names = ["delete", "invite", "restrict", "pin"].iter().map(|it| it.to_string()).collect();
+    }
+
+    let mut rights = ChatAdministratorRights {
+        can_manage_chat: true,
+        ..Default::default()
+    };
+
+    for right in names {
+        match right.as_str() {
+            "info" => rights.can_change_info = true,
+            "post" => rights.can_post_messages = true,
+            "edit" => rights.can_edit_messages = true,
+            "delete" => rights.can_delete_messages = true,
+            "invite" => rights.can_invite_users = true,
+            "restrict" => rights.can_restrict_members = true,
+            "pin" => rights.can_pin_messages = true,
+            "video" => rights.can_manage_video_chats = true,
+            "promote" => rights.can_promote_members = true,
+            "anonymous" => rights.is_anonymous = true,
+            other => cli::exit_with_usage(&format!("unknown administrator right '{}'", other)),
+        }
+    }
+
+    rights
+}
+
+impl PrettyPrint for ChatMemberStatus {
+    fn pretty_format(&self) -> String {
+        match self {
+            ChatMemberStatus::ChatMemberStatusCreator { custom_title, .. } => format!("owner {}", custom_title),
+            ChatMemberStatus::ChatMemberStatusAdministrator { custom_title, .. } => format!("admin {}", custom_title),
+            ChatMemberStatus::ChatMemberStatusMember => "".to_owned(),
+            ChatMemberStatus::ChatMemberStatusRestricted { restricted_until_date: 0, .. } => "restricted".to_owned(),
+            ChatMemberStatus::ChatMemberStatusRestricted { restricted_until_date, .. } => {
+                format!("restricted until {}", view::datetime(*restricted_until_date).format("%m.%d %H:%M"))
+            }
+            ChatMemberStatus::ChatMemberStatusLeft => "left".to_owned(),
+            ChatMemberStatus::ChatMemberStatusBanned { banned_until_date: 0 } => "banned".to_owned(),
+            ChatMemberStatus::ChatMemberStatusBanned { banned_until_date } => {
+                format!("banned until {}", view::datetime(*banned_until_date).format("%m.%d %H:%M"))
+            }
+        }
+    }
+}
+
+async fn info(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let chat = manager.get_chat(args.chat(manager, 1).await).await.unwrap_or_else(|error| cli::exit_with_error(error));
+
+    println!("{}", chat.title.bold().blue());
+
+    match chat.chat_type {
+        ChatType::ChatTypeBasicGroup { basic_group_id } => {
+            let group = match manager.basic_group(basic_group_id) {
+                Some(group) => group,
+                None => manager
+                    .get_basic_group(basic_group_id)
+                    .await
+                    .unwrap_or_else(|error| cli::exit_with_error(error)),
+            };
+            let full_info = manager.get_basic_group_full_info(basic_group_id).await?;
+
+            println!("{}", full_info.description);
+            println!("{} members, you are {}", group.member_count, group.status.pretty_format());
+            if let Some(link) = full_info.invite_link {
+                println!("{}", link.invite_link.underline());
+            }
+        }
+        ChatType::ChatTypeSupergroup { supergroup_id, is_channel } => {
+            let supergroup = match manager.supergroup(supergroup_id) {
+                Some(supergroup) => supergroup,
+                None => manager.get_supergroup(supergroup_id).await.unwrap_or_else(|error| cli::exit_with_error(error)),
+            };
+            let full_info = manager.get_supergroup_full_info(supergroup_id).await?;
+
+            println!("{}", full_info.description);
+            println!(
+                "{} {}, {} administrators, {} restricted, {} banned",
+                full_info.member_count,
+                if is_channel { "subscribers" } else { "members" },
+                full_info.administrator_count,
+                full_info.restricted_count,
+                full_info.banned_count
+            );
+            println!("you are {}", supergroup.status.pretty_format());
+            if !supergroup.username.is_empty() {
+                println!("{}", format!("https://t.me/{}", supergroup.username).underline());
+            }
+            if let Some(link) = full_info.invite_link {
+                println!("{}", link.invite_link.underline());
+            }
+        }
+        _ => cli::exit_with_usage("not a group or channel"),
+    }
+
+    Ok(())
+}
+
+async fn print_members(manager: &Manager, members: &[ChatMember]) {
+    for member in members {
+        let (id, name) = match &member.member_id {
+            MessageSender::MessageSenderUser(user) => {
+                let user = manager.get_user(user.user_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+                let username = if user.username.is_empty() {
+                    "".to_owned()
+                } else {
+                    format!(" @{}", user.username)
+                };
+                (user.id, format!("{}{}", view::full_name(&user), username))
+            }
+            MessageSender::MessageSenderChat(chat) => {
+                let chat = manager.get_chat(chat.chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+                (chat.id, chat.title)
+            }
+        };
+
+        println!(
+            "{} {} {}",
+            name.bold(),
+            format!("#{}", id).bright_black(),
+            member.status.pretty_format().magenta()
+        );
+    }
+}
+
+async fn members(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let manager = &manager.with_priority(Priority::Background);
+    let chat_id = args.chat(manager, 1).await;
+    let query = args.rest(2);
+    let limit = args
+        .value("limit")
+        .map(|it| it.parse::<i32>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric limit")))
+        .unwrap_or(200);
+
+    let chat = manager.get_chat(chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+
+    // Supergroups are paged through in full, anything else only supports searching the first 200
+    if let ChatType::ChatTypeSupergroup { supergroup_id, .. } = chat.chat_type {
+        let filter = match args.value("filter") {
+            None if query.is_empty() => SupergroupMembersFilter::SupergroupMembersFilterRecent,
+            None => SupergroupMembersFilter::SupergroupMembersFilterSearch { query },
+            Some("admins") => SupergroupMembersFilter::SupergroupMembersFilterAdministrators,
+            Some("bots") => SupergroupMembersFilter::SupergroupMembersFilterBots,
+            Some("contacts") => SupergroupMembersFilter::SupergroupMembersFilterContacts { query },
+            Some("restricted") => SupergroupMembersFilter::SupergroupMembersFilterRestricted { query },
+            Some("banned") => SupergroupMembersFilter::SupergroupMembersFilterBanned { query },
+            Some(other) => cli::exit_with_usage(&format!("unknown member filter '{}'", other)),
+        };
+
+        let mut offset = 0;
+        while offset < limit {
+            let page = manager
+                .get_supergroup_members(supergroup_id, filter.clone(), offset, (limit - offset).min(200))
+                .await?;
+            if page.members.is_empty() {
+                break;
+            }
+
+            offset += page.members.len() as i32;
+            print_members(manager, &page.members).await;
+        }
+    } else {
+        let filter = match args.value("filter") {
+            None => None,
+            Some("admins") => Some(ChatMembersFilter::ChatMembersFilterAdministrators),
+            Some("bots") => Some(ChatMembersFilter::ChatMembersFilterBots),
+            Some("contacts") => Some(ChatMembersFilter::ChatMembersFilterContacts),
+            Some("restricted") => Some(ChatMembersFilter::ChatMembersFilterRestricted),
+            Some("banned") => Some(ChatMembersFilter::ChatMembersFilterBanned),
+            Some(other) => cli::exit_with_usage(&format!("unknown member filter '{}'", other)),
+        };
+
+        let members = manager
+            .search_chat_members(chat_id, &query, filter, limit.min(200))
+            .await?;
+        print_members(manager, &members.members).await;
+    }
+
+    Ok(())
+}
+
+async fn links(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let chat_id = args.chat(manager, 1).await;
+    let my_id = match manager.my_id() {
+        Some(id) => id,
+        None => manager.get_me().await?.id,
+    };
+    let links = manager
+        .get_chat_invite_links(chat_id, my_id, args.flag("revoked"), 100)
+        .await?;
+
+    for link in links.invite_links {
+        let mut details = vec![format!("{} joined", link.member_count)];
+        if link.is_primary {
+            details.push("primary".to_owned());
+        }
+        if link.member_limit > 0 {
+            details.push(format!("limit {}", link.member_limit));
+        }
+        if link.expiration_date > 0 {
+            details.push(format!("expires {}", view::datetime(link.expiration_date).format("%m.%d %H:%M")));
+        }
+        if link.creates_join_request {
+            details.push(format!("{} pending requests", link.pending_join_request_count));
+        }
+
+        println!(
+            "{} {} {}",
+            link.invite_link.underline(),
+            link.name.bold(),
+            format!("({})", details.join(", ")).bright_black()
+        );
+    }
+
+    Ok(())
+}
+
+async fn create_link(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let member_limit = args
+        .value("limit")
+        .map(|it| it.parse::<i32>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric member limit")))
+        .unwrap_or(0);
+
+    let link = manager
+        .create_chat_invite_link(
+            args.chat(manager, 1).await,
+            args.value("name").unwrap_or_default(),
+            until(args),
+            member_limit,
+            args.flag("request"),
+        )
+        .await?;
+
+    println!("{}", link.invite_link);
+    Ok(())
+}
+
+async fn requests(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let chat_id = args.chat(manager, 1).await;
+    let mut updates = manager.updates();
+
+    let requests = manager.get_chat_join_requests(chat_id, "", 100).await?;
+    for request in requests.requests {
+        let user = manager.get_user(request.user_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+        println!(
+            "{}{} {} {}",
+            view::datetime(request.date).pretty_format().bold().bright_black(),
+            view::full_name(&user).bold(),
+            format!("#{}", user.id).bright_black(),
+            request.bio
+        );
+    }
+
+    if !args.flag("watch") {
+        return Ok(());
+    }
+
+    while let Some(update) = updates.next().await {
+        if let TgEventData::UpdateNewChatJoinRequest(update) = update {
+            if update.chat_id != chat_id {
+                continue;
+            }
+
+            // Keep watching, the request is still there to be listed later
+            let user = match manager.get_user(update.request.user_id).await {
+                Ok(user) => user,
+                Err(error) => {
+                    eprintln!("Can't get user #{} asking to join: {}", update.request.user_id, error.message);
+                    continue;
+                }
+            };
+            println!(
+                "{}{} {} {}",
+                view::datetime(update.request.date).pretty_format().bold().bright_black(),
+                view::full_name(&user).bold(),
+                format!("#{}", user.id).bright_black(),
+                update.request.bio
+            );
+        }
+    }
+
+    Ok(())
+}
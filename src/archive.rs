@@ -0,0 +1,769 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{Chat, ChatType, Error, Message, MessageContent, MessageSender, TgEventData, User},
+    manager::Manager,
+    scheduler::Priority,
+};
+use crate::view;
+use colored::Colorize;
+use futures::StreamExt;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS chats (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        type TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS users (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        username TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS messages (
+        chat_id INTEGER NOT NULL,
+        id INTEGER NOT NULL,
+        date INTEGER NOT NULL,
+        edit_date INTEGER NOT NULL,
+        sender_id INTEGER NOT NULL,
+        reply_to_message_id INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        content TEXT NOT NULL,
+        is_deleted INTEGER NOT NULL DEFAULT 0,
+        UNIQUE (chat_id, id)
+    );
+
+    CREATE INDEX IF NOT EXISTS messages_by_date ON messages (date);
+
+    -- How far history of a chat was archived: everything from oldest_id to newest_id, and before that too if complete
+    CREATE TABLE IF NOT EXISTS sync (
+        chat_id INTEGER PRIMARY KEY,
+        newest_id INTEGER NOT NULL,
+        oldest_id INTEGER NOT NULL,
+        is_complete INTEGER NOT NULL
+    );
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+        text,
+        content = 'messages',
+        content_rowid = 'rowid',
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS messages_ai AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts (rowid, text) VALUES (new.rowid, new.text);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_ad AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_au AFTER UPDATE OF text ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+        INSERT INTO messages_fts (rowid, text) VALUES (new.rowid, new.text);
+    END;
+";
+
+/// Where the archive lives, next to TDLib's own files.
+fn path() -> PathBuf {
+    dirs::home_dir()
+        .expect("Can't get home directory")
+        .join(".config/rust-messenger/archive.sqlite")
+}
+
+/// Everything worth finding in a message: text, captions, file names and polls.
+fn searchable_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::MessageText(body) => body.text.text.clone(),
+        MessageContent::MessageDocument(body) => format!("{} {}", body.document.file_name, body.caption.text),
+        MessageContent::MessagePhoto(body) => body.caption.text.clone(),
+        MessageContent::MessageVideo(body) => format!("{} {}", body.video.file_name, body.caption.text),
+        MessageContent::MessageAudio(body) => format!(
+            "{} {} {} {}",
+            body.audio.performer, body.audio.title, body.audio.file_name, body.caption.text
+        ),
+        MessageContent::MessageVoiceNote(body) => body.caption.text.clone(),
+        MessageContent::MessagePoll(body) => std::iter::once(body.poll.question.as_str())
+            .chain(body.poll.options.iter().map(|option| option.text.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => "".to_owned(),
+    }
+    .trim()
+    .to_owned()
+}
+
+fn sender_id(message: &Message) -> i64 {
+    match &message.sender_id {
+        MessageSender::MessageSenderUser(user) => user.user_id,
+        MessageSender::MessageSenderChat(chat) => chat.chat_id,
+    }
+}
+
+/// What a write to the archive returned, or `None` after logging why it failed.
+fn logged<T>(result: rusqlite::Result<T>) -> Option<T> {
+    result.map_err(|error| eprintln!("Can't update archive: {}", error)).ok()
+}
+
+/// Turns what the user typed into an FTS5 query: every word has to match, as a prefix of a word in the message.
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub struct Hit {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub date: i32,
+    pub chat_title: String,
+    pub sender_name: String,
+    /// Part of the text around the match, with matches between `\u{1}` and `\u{2}`
+    pub snippet: String,
+}
+
+/// Local SQLite archive of every message, chat and user rsm has seen.
+pub struct Archive {
+    connection: Connection,
+}
+
+impl Archive {
+    pub fn open() -> Archive {
+        let path = path();
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).expect("Can't create archive directory");
+        }
+        Archive::new(Connection::open(path).expect("Can't open archive"))
+    }
+
+    fn new(connection: Connection) -> Archive {
+        connection.execute_batch(SCHEMA).expect("Can't create archive tables");
+        Archive { connection }
+    }
+
+    pub fn save_chat(&self, chat: &Chat) -> rusqlite::Result<()> {
+        let chat_type = match chat.chat_type {
+            ChatType::ChatTypePrivate { .. } => "private",
+            ChatType::ChatTypeSecret { .. } => "secret",
+            ChatType::ChatTypeBasicGroup { .. } => "group",
+            ChatType::ChatTypeSupergroup { is_channel: false, .. } => "supergroup",
+            ChatType::ChatTypeSupergroup { is_channel: true, .. } => "channel",
+        };
+
+        self.connection
+            .execute(
+                "INSERT INTO chats (id, title, type) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET title = excluded.title",
+                params![chat.id, chat.title, chat_type],
+            )
+            .map(|_| ())
+    }
+
+    /// Keeps the title of an archived chat current, chats that aren't archived yet are left to their next message.
+    pub fn rename_chat(&self, chat_id: i64, title: &str) -> rusqlite::Result<()> {
+        self.connection
+            .execute("UPDATE chats SET title = ?2 WHERE id = ?1", params![chat_id, title])
+            .map(|_| ())
+    }
+
+    pub fn has_chat(&self, chat_id: i64) -> rusqlite::Result<bool> {
+        self.connection
+            .query_row("SELECT 1 FROM chats WHERE id = ?1", params![chat_id], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+    }
+
+    pub fn save_user(&self, user: &User) -> rusqlite::Result<()> {
+        self.connection
+            .execute(
+                "INSERT INTO users (id, name, username) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET name = excluded.name, username = excluded.username",
+                params![user.id, view::full_name(user), user.username],
+            )
+            .map(|_| ())
+    }
+
+    pub fn save_message(&self, message: &Message) -> rusqlite::Result<()> {
+        let content = serde_json::to_string(&message.content).expect("Can't serialize message content");
+
+        self.connection
+            .execute(
+                "INSERT INTO messages (chat_id, id, date, edit_date, sender_id, reply_to_message_id, text, content)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (chat_id, id) DO UPDATE
+                 SET edit_date = excluded.edit_date, text = excluded.text, content = excluded.content",
+                params![
+                    message.chat_id,
+                    message.id,
+                    message.date,
+                    message.edit_date,
+                    sender_id(message),
+                    message.reply_to_message_id,
+                    searchable_text(&message.content),
+                    content
+                ],
+            )
+            .map(|_| ())
+    }
+
+    /// Keeps deleted messages around, that's what an archive is for, but marks them.
+    pub fn mark_deleted(&self, chat_id: i64, message_ids: &[i64]) -> rusqlite::Result<()> {
+        for id in message_ids {
+            self.connection.execute(
+                "UPDATE messages SET is_deleted = 1 WHERE chat_id = ?1 AND id = ?2",
+                params![chat_id, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Drops the temporary copy of a message that was being sent, once it got its real id.
+    pub fn forget(&self, chat_id: i64, message_id: i64) -> rusqlite::Result<()> {
+        self.connection
+            .execute(
+                "DELETE FROM messages WHERE chat_id = ?1 AND id = ?2",
+                params![chat_id, message_id],
+            )
+            .map(|_| ())
+    }
+
+    /// `(newest_id, oldest_id, is_complete)` of the archived history of a chat.
+    fn sync_state(&self, chat_id: i64) -> Option<(i64, i64, bool)> {
+        self.connection
+            .query_row(
+                "SELECT newest_id, oldest_id, is_complete FROM sync WHERE chat_id = ?1",
+                params![chat_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .expect("Can't read archive")
+    }
+
+    fn set_sync_state(&self, chat_id: i64, newest_id: i64, oldest_id: i64, is_complete: bool) {
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO sync (chat_id, newest_id, oldest_id, is_complete) VALUES (?1, ?2, ?3, ?4)",
+                params![chat_id, newest_id, oldest_id, is_complete],
+            )
+            .expect("Can't update archive");
+    }
+
+    /// Archived chats whose title contains `title`, for filtering searches without asking TDLib.
+    pub fn find_chats(&self, title: &str) -> Vec<(i64, String)> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, title FROM chats WHERE title LIKE '%' || ?1 || '%' ORDER BY title")
+            .expect("Can't read archive");
+        let rows = statement
+            .query_map(params![title], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("Can't read archive");
+        rows.collect::<Result<_, _>>().expect("Can't read archive")
+    }
+
+    pub fn search(&self, query: &str, chat_id: Option<i64>, limit: i64) -> Vec<Hit> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT m.chat_id, m.id, m.date, coalesce(c.title, ''), coalesce(u.name, s.title, ''),
+                        snippet(messages_fts, 0, char(1), char(2), '…', 16)
+                 FROM messages_fts
+                 JOIN messages m ON m.rowid = messages_fts.rowid
+                 LEFT JOIN chats c ON c.id = m.chat_id
+                 LEFT JOIN users u ON u.id = m.sender_id
+                 LEFT JOIN chats s ON s.id = m.sender_id
+                 WHERE messages_fts MATCH ?1 AND (?2 IS NULL OR m.chat_id = ?2)
+                 ORDER BY m.date DESC
+                 LIMIT ?3",
+            )
+            .expect("Can't read archive");
+
+        let rows = statement
+            .query_map(params![fts_query(query), chat_id, limit], |row| {
+                Ok(Hit {
+                    chat_id: row.get(0)?,
+                    message_id: row.get(1)?,
+                    date: row.get(2)?,
+                    chat_title: row.get(3)?,
+                    sender_name: row.get(4)?,
+                    snippet: row.get(5)?,
+                })
+            })
+            .expect("Can't search archive");
+        rows.collect::<Result<_, _>>().expect("Can't search archive")
+    }
+
+    /// Archives what an update carries. Chats and senders are fetched the first time they show up, the error
+    /// says why they couldn't be. They're tried again with their next message.
+    /// Writes that fail are logged and the update skipped, rather than taking down the command it runs alongside.
+    pub async fn apply(&self, manager: &Manager, update: &TgEventData) -> Result<(), Error> {
+        let written = match update {
+            TgEventData::UpdateNewMessage(update) => return self.save(manager, &update.message).await,
+            TgEventData::UpdateMessageSendSucceeded(update) => {
+                logged(self.forget(update.message.chat_id, update.old_message_id));
+                return self.save(manager, &update.message).await;
+            }
+            TgEventData::UpdateMessageContent(update) => {
+                let content = serde_json::to_string(&update.new_content).expect("Can't serialize message content");
+                self.connection
+                    .execute(
+                        "UPDATE messages SET text = ?3, content = ?4 WHERE chat_id = ?1 AND id = ?2",
+                        params![update.chat_id, update.message_id, searchable_text(&update.new_content), content],
+                    )
+                    .map(|_| ())
+            }
+            TgEventData::UpdateMessageEdited(update) => self
+                .connection
+                .execute(
+                    "UPDATE messages SET edit_date = ?3 WHERE chat_id = ?1 AND id = ?2",
+                    params![update.chat_id, update.message_id, update.edit_date],
+                )
+                .map(|_| ()),
+            TgEventData::UpdateDeleteMessages(update) if update.is_permanent => {
+                self.mark_deleted(update.chat_id, &update.message_ids)
+            }
+            TgEventData::UpdateUser(update) => self.save_user(&update.user),
+            TgEventData::UpdateNewChat(update) => self.save_chat(&update.chat),
+            TgEventData::UpdateChatTitle(update) => self.rename_chat(update.chat_id, &update.title),
+            _ => Ok(()),
+        };
+        logged(written);
+        Ok(())
+    }
+
+    async fn save(&self, manager: &Manager, message: &Message) -> Result<(), Error> {
+        if logged(self.save_message(message)).is_none() {
+            return Ok(());
+        }
+        if logged(self.has_chat(message.chat_id)) == Some(false) {
+            logged(self.save_chat(&manager.get_chat(message.chat_id).await?));
+        }
+        self.save_sender(manager, message).await
+    }
+
+    async fn save_sender(&self, manager: &Manager, message: &Message) -> Result<(), Error> {
+        if let MessageSender::MessageSenderUser(sender) = &message.sender_id {
+            let known = self
+                .connection
+                .query_row("SELECT 1 FROM users WHERE id = ?1", params![sender.user_id], |_| Ok(()))
+                .optional()
+                .map(|row| row.is_some());
+            if logged(known) == Some(false) {
+                logged(self.save_user(&manager.get_user(sender.user_id).await?));
+            }
+        }
+        Ok(())
+    }
+
+    /// Archives a page of history in one transaction, together with `(newest_id, oldest_id)` the sync got to
+    /// if given, so an interrupted sync neither leaves half a page nor skips one.
+    fn save_page(&self, chat_id: i64, page: &[Message], progress: Option<(i64, i64)>) {
+        let transaction = self.connection.unchecked_transaction().expect("Can't update archive");
+        for message in page {
+            self.save_message(message).expect("Can't archive message");
+        }
+        if let Some((newest_id, oldest_id)) = progress {
+            self.set_sync_state(chat_id, newest_id, oldest_id, false);
+        }
+        transaction.commit().expect("Can't update archive");
+    }
+
+    /// Archives history of the chat that isn't archived yet: what's newer than the last sync first,
+    /// then what's older than anything archived, until the beginning of the chat.
+    /// What's archived stays archived when it fails, the next sync goes on from there.
+    pub async fn sync(&self, manager: &Manager, chat: &Chat) -> Result<usize, Error> {
+        self.save_chat(chat).expect("Can't archive chat");
+        let chat_id = chat.id;
+        self.sync_history(chat_id, |from_message_id| async move {
+            let page = manager.get_chat_history(chat_id, from_message_id, 100).await?;
+            for message in &page {
+                // Not worth stopping for, search shows the message without the sender's name
+                if let Err(error) = self.save_sender(manager, message).await {
+                    eprintln!("Can't archive sender of message {}: {}", message.id, error.message);
+                }
+            }
+            Ok(page)
+        })
+        .await
+    }
+
+    /// The bookkeeping of `sync`, with `history` giving the page of messages older than the one with the given id,
+    /// newest first, or the newest messages for 0.
+    async fn sync_history<F>(&self, chat_id: i64, mut history: impl FnMut(i64) -> F) -> Result<usize, Error>
+    where
+        F: Future<Output = Result<Vec<Message>, Error>>,
+    {
+        let state = self.sync_state(chat_id);
+        let mut count = 0;
+
+        // Newest first, down to where the last sync started
+        let mut newest_id = state.map(|(newest_id, _, _)| newest_id).unwrap_or_default();
+        let mut oldest_id = state.map(|(_, oldest_id, _)| oldest_id).unwrap_or_default();
+        let mut top = 0;
+        let mut from_message_id = 0;
+        loop {
+            let page = history(from_message_id).await?;
+            let page: Vec<Message> = page.into_iter().filter(|message| message.id > newest_id).collect();
+            if page.is_empty() {
+                break;
+            }
+
+            count += page.len();
+            top = top.max(page[0].id);
+            from_message_id = page[page.len() - 1].id;
+
+            // The very first sync goes on to the beginning, remember how far it got in case it's interrupted
+            if state.is_none() {
+                oldest_id = from_message_id;
+                self.save_page(chat_id, &page, Some((top, oldest_id)));
+            } else {
+                self.save_page(chat_id, &page, None);
+            }
+        }
+        newest_id = newest_id.max(top);
+
+        let is_complete = state.map(|(_, _, is_complete)| is_complete).unwrap_or(false);
+        if state.is_some() && !is_complete {
+            loop {
+                let page = history(oldest_id).await?;
+                if page.is_empty() {
+                    break;
+                }
+
+                count += page.len();
+                oldest_id = page[page.len() - 1].id;
+                self.save_page(chat_id, &page, Some((newest_id, oldest_id)));
+            }
+        }
+
+        self.set_sync_state(chat_id, newest_id, oldest_id, true);
+        Ok(count)
+    }
+}
+
+/// `rsm archive sync [chat]` and `rsm archive watch`.
+pub async fn run(manager: &Manager, args: &Args) {
+    let archive = Archive::open();
+
+    match args.positional(0) {
+        Some("sync") => {
+            let manager = &manager.with_priority(Priority::Background);
+            let chat_ids = match args.positional(1) {
+                Some(_) => vec![args.chat(manager, 1).await],
+                None => manager.get_chats(200).await.unwrap_or_else(|error| cli::exit_with_error(error)).chat_ids,
+            };
+
+            // One chat failing doesn't keep the others from being archived
+            for chat_id in chat_ids {
+                let chat = match manager.get_chat(chat_id).await {
+                    Ok(chat) => chat,
+                    Err(error) => {
+                        eprintln!("Can't get chat #{}: {}", chat_id, error.message);
+                        continue;
+                    }
+                };
+                match archive.sync(manager, &chat).await {
+                    Ok(count) => println!("{} {}", chat.title.bold(), format!("+{}", count).bright_black()),
+                    Err(error) => eprintln!("{} {}", chat.title.bold(), error.message.red()),
+                }
+            }
+        }
+        Some("watch") => {
+            let mut updates = manager.updates();
+            let interrupt = tokio::signal::ctrl_c();
+            tokio::pin!(interrupt);
+
+            loop {
+                tokio::select! {
+                    update = updates.next() => match update {
+                        Some(update) => {
+                            if let Err(error) = archive.apply(manager, &update).await {
+                                eprintln!("Can't archive: {}", error.message);
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = &mut interrupt => break,
+                }
+            }
+        }
+        Some(other) => cli::exit_with_usage(&format!("unknown archive command '{}'", other)),
+        None => cli::exit_with_usage("no archive command given"),
+    }
+}
+
+/// Runs a command, archiving what arrives meanwhile like `rsm archive watch` if it's one that keeps running:
+/// the chat list, a conversation, a thread or `rsm notify`.
+pub async fn alongside(manager: &Manager, args: &Args, command: impl Future<Output = ()>) {
+    if !matches!(args.command.as_deref(), None | Some("chats" | "chat" | "thread" | "notify")) {
+        return command.await;
+    }
+
+    let archive = Archive::open();
+    let mut updates = manager.updates();
+    // Both run in this task, interleaved, with the command's requests going before the archive's
+    let background = manager.with_priority(Priority::Background);
+    let archiving = async {
+        // The command has the screen, what's missing is fetched again with the next message
+        while let Some(update) = updates.next().await {
+            archive.apply(&background, &update).await.unwrap_or_default();
+        }
+    };
+    tokio::pin!(command);
+
+    tokio::select! {
+        _ = &mut command => (),
+        _ = archiving => command.await,
+    }
+}
+
+/// `rsm grep <query> [--chat <title or id>] [--limit <n>]`, searching the archive without connecting to Telegram.
+pub fn grep(args: &Args) {
+    let query = args.rest(0);
+    if query.is_empty() {
+        cli::exit_with_usage("nothing to search for");
+    }
+
+    let archive = Archive::open();
+    let limit = args
+        .value("limit")
+        .map(|it| it.parse::<i64>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric limit")))
+        .unwrap_or(50);
+
+    let chat_id = args.value("chat").map(|chat| match chat.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => match archive.find_chats(chat).as_slice() {
+            [(id, _)] => *id,
+            [] => cli::exit_with_usage(&format!("no archived chat matches '{}'", chat)),
+            chats => {
+                let candidates = chats
+                    .iter()
+                    .map(|(id, title)| format!("    {} #{}", title, id))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                eprintln!("'{}' is ambiguous, it matches:\n{}", chat, candidates);
+                std::process::exit(2)
+            }
+        },
+    });
+
+    for hit in archive.search(&query, chat_id, limit) {
+        let snippet = hit
+            .snippet
+            .split('\u{1}')
+            .enumerate()
+            .map(|(index, part)| match (index, part.split_once('\u{2}')) {
+                (0, _) | (_, None) => part.to_owned(),
+                (_, Some((matched, rest))) => format!("{}{}", matched.bold().yellow(), rest),
+            })
+            .collect::<String>();
+
+        println!(
+            "{}{} {} {}",
+            view::datetime(hit.date).format("%Y-%m-%d %H:%M ").to_string().bright_black(),
+            hit.chat_title.bold().blue(),
+            hit.sender_name.bright_black(),
+            format!("#{}/{}", hit.chat_id, hit.message_id).bright_black()
+        );
+        println!("{}\n", snippet.replace('\n', " "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::{
+        api::{UpdateChatTitle, UpdateNewChat},
+        fixtures::{self, message},
+        manager::TIMEOUT_ERROR_CODE,
+    };
+    use serde_json::json;
+
+    /// Chat history with messages 1 to `count`, served like TDLib does in pages of up to 100, newest first.
+    fn history(count: i64, from_message_id: i64) -> Result<Vec<Message>, Error> {
+        let newest = if from_message_id == 0 { count } else { from_message_id - 1 };
+        Ok((1..=newest).rev().take(100).map(|id| message(id, "")).collect())
+    }
+
+    fn archived(archive: &Archive) -> i64 {
+        archive
+            .connection
+            .query_row("SELECT count(*) FROM messages", [], |row| row.get(0))
+            .expect("Can't count messages")
+    }
+
+    #[test]
+    fn queries_match_every_word_as_prefix() {
+        assert_eq!(fts_query("hello"), r#""hello"*"#);
+        assert_eq!(fts_query("  hello   wor "), r#""hello"* "wor"*"#);
+        assert_eq!(fts_query(""), "");
+    }
+
+    #[test]
+    fn queries_keep_fts_syntax_literal() {
+        assert_eq!(fts_query(r#"say "hi""#), r#""say"* """hi"""*"#);
+        assert_eq!(fts_query("a OR b NOT c*"), r#""a"* "OR"* "b"* "NOT"* "c*"*"#);
+        assert_eq!(fts_query("title:x -y"), r#""title:x"* "-y"*"#);
+    }
+
+    #[test]
+    fn search_finds_prefixes_without_diacritics() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        archive.save_message(&message(1, "Meet at the café tomorrow")).expect("Can't archive message");
+        archive.save_message(&message(2, "cafeteria is closed")).expect("Can't archive message");
+        archive.save_message(&message(3, "see you")).expect("Can't archive message");
+
+        let ids = |query: &str| archive.search(query, None, 10).iter().map(|hit| hit.message_id).collect::<Vec<_>>();
+        assert_eq!(ids("cafe"), vec![2, 1]);
+        assert_eq!(ids("cafe tomorrow"), vec![1]);
+        assert_eq!(ids(r#"OR "see"#), Vec::<i64>::new());
+        assert!(archive.search("cafe", Some(2), 10).is_empty());
+
+        // Edits replace what's found
+        archive.save_message(&message(3, "see you at the cafe")).expect("Can't archive message");
+        assert_eq!(ids("cafe"), vec![3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn first_sync_goes_to_the_beginning() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+
+        let count = archive
+            .sync_history(1, |from_message_id| async move { history(250, from_message_id) })
+            .await
+            .expect("Can't sync");
+
+        assert_eq!(count, 250);
+        assert_eq!(archived(&archive), 250);
+        assert_eq!(archive.sync_state(1), Some((250, 1, true)));
+    }
+
+    #[tokio::test]
+    async fn first_sync_remembers_each_page() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        let archive = &archive;
+        let mut progress = Vec::new();
+
+        archive
+            .sync_history(1, |from_message_id| {
+                progress.push((archived(archive), archive.sync_state(1)));
+                async move { history(250, from_message_id) }
+            })
+            .await
+            .expect("Can't sync");
+
+        assert_eq!(
+            progress,
+            vec![
+                (0, None),
+                (100, Some((250, 151, false))),
+                (200, Some((250, 51, false))),
+                (250, Some((250, 1, false))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn later_syncs_only_fetch_new_messages() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        archive
+            .sync_history(1, |from_message_id| async move { history(250, from_message_id) })
+            .await
+            .expect("Can't sync");
+        let mut requests = Vec::new();
+
+        let count = archive
+            .sync_history(1, |from_message_id| {
+                requests.push(from_message_id);
+                async move { history(280, from_message_id) }
+            })
+            .await
+            .expect("Can't sync");
+
+        assert_eq!(count, 30);
+        assert_eq!(requests, vec![0, 251]);
+        assert_eq!(archived(&archive), 280);
+        assert_eq!(archive.sync_state(1), Some((280, 1, true)));
+    }
+
+    #[tokio::test]
+    async fn interrupted_sync_continues_where_it_stopped() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        archive.save_page(1, &history(250, 0).expect("Can't get history"), Some((250, 151)));
+
+        let count = archive
+            .sync_history(1, |from_message_id| async move { history(260, from_message_id) })
+            .await
+            .expect("Can't sync");
+
+        assert_eq!(count, 160);
+        assert_eq!(archived(&archive), 260);
+        assert_eq!(archive.sync_state(1), Some((260, 1, true)));
+    }
+
+    #[tokio::test]
+    async fn failed_sync_keeps_what_it_archived() {
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+
+        let failed = archive
+            .sync_history(1, |from_message_id| async move {
+                match from_message_id {
+                    0 => history(250, 0),
+                    _ => Err(Error {
+                        code: TIMEOUT_ERROR_CODE,
+                        message: "No answer from TDLib in 60 seconds".to_owned(),
+                    }),
+                }
+            })
+            .await;
+
+        assert_eq!(failed.map_err(|error| error.code).err(), Some(TIMEOUT_ERROR_CODE));
+        assert_eq!(archived(&archive), 100);
+        assert_eq!(archive.sync_state(1), Some((250, 151, false)));
+
+        let count = archive
+            .sync_history(1, |from_message_id| async move { history(250, from_message_id) })
+            .await
+            .expect("Can't sync");
+        assert_eq!(count, 150);
+        assert_eq!(archive.sync_state(1), Some((250, 1, true)));
+    }
+
+    #[tokio::test]
+    async fn apply_keeps_chat_titles_current() {
+        let manager = Manager::new();
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        let chat = fixtures::chat(json!({ "@type": "chatTypePrivate", "user_id": 2 }));
+        let title = |archive: &Archive| archive.find_chats("").into_iter().map(|(_, title)| title).collect::<Vec<_>>();
+
+        let new_chat = TgEventData::UpdateNewChat(UpdateNewChat { chat });
+        archive.apply(&manager, &new_chat).await.expect("Can't archive chat");
+        assert_eq!(title(&archive), vec!["Chat"]);
+
+        let renamed = TgEventData::UpdateChatTitle(UpdateChatTitle {
+            chat_id: 1,
+            title: "Renamed".to_owned(),
+        });
+        archive.apply(&manager, &renamed).await.expect("Can't archive title");
+        assert_eq!(title(&archive), vec!["Renamed"]);
+    }
+
+    #[tokio::test]
+    async fn apply_skips_updates_it_cannot_write() {
+        let manager = Manager::new();
+        let archive = Archive::new(Connection::open_in_memory().expect("Can't open database"));
+        archive.connection.execute_batch("DROP TABLE chats").expect("Can't drop table");
+
+        let renamed = TgEventData::UpdateChatTitle(UpdateChatTitle {
+            chat_id: 1,
+            title: "Renamed".to_owned(),
+        });
+        assert!(archive.apply(&manager, &renamed).await.is_ok());
+    }
+}
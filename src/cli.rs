@@ -0,0 +1,267 @@
+use crate::telegram::{
+    api::{ChatType, Error},
+    manager::Manager,
+};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Options that never take a value, so `--flag 42` leaves `42` as a positional argument.
+const SWITCHES: &[&str] = &["everyone", "copy", "no-caption", "remove", "big", "multiple", "public", "revoke", "revoked", "request", "watch", "share-phone", "mentions", "media", "disabled", "td-log", "reset"];
+
+pub const USAGE: &str = "usage:
+    rsm                                         list recent chats
+
+    <chat> is a numeric id, @username, t.me link or (a prefix of) the chat title
+
+    rsm chat <chat>                             open a conversation and follow it live
+    rsm thread <chat> <message_id>              open replies to a message or comments to a channel post
+    rsm send <chat> [text] [--file <path>] [--thumbnail <path>]
+                    [--reply <message_id>] [--thread <message_id>]
+                                                without text or file it's read from stdin, showing you as typing
+    rsm edit <chat> <message_id> <text>
+    rsm delete <chat> <message_id>... [--everyone]
+    rsm forward <to_chat> <from_chat> <message_id>... [--copy] [--no-caption]
+    rsm read <chat>                             mark everything in the chat as read
+    rsm unread <chat>                           mark the chat as unread
+    rsm react <chat> <message_id> <emoji> [--big] [--remove]
+    rsm reactions <chat>                        list reactions available in the chat
+    rsm poll <chat> <question> <option> <option>... [--multiple] [--public] [--quiz <correct option>]
+    rsm vote <chat> <message_id> [option]... vote for options by number, none retracts the vote
+    rsm stop-poll <chat> <message_id>
+    rsm search <query> [--chat <chat> [--from <user_id>]] [--limit <n>]
+               [--filter documents|photos|videos|media|audio|voice|gifs|links|mentions|pinned]
+    rsm admin info <chat>                       describe a group or channel
+    rsm admin members <chat> [query] [--filter admins|bots|contacts|restricted|banned] [--limit <n>]
+    rsm admin add|unban|demote|approve|decline <chat> <user_id>
+    rsm admin ban <chat> <user_id> [--until <30m|12h|7d>] [--revoke]
+    rsm admin restrict <chat> <user_id> [--until <duration>]
+                       [--allow messages,media,polls,other,previews,info,invite,pin]
+    rsm admin promote <chat> <user_id> [--title <title>]
+                      [--rights info,post,edit,delete,invite,restrict,pin,video,promote,anonymous]
+    rsm admin title|description <chat> <text>
+    rsm admin photo <chat> [path]               set the chat photo, none removes it
+    rsm admin links <chat> [--revoked]          list your invite links
+    rsm admin create-link <chat> [--name <name>] [--until <duration>] [--limit <n>] [--request]
+    rsm admin revoke-link <chat> <link>
+    rsm admin requests <chat> [--watch]         list pending join requests, optionally waiting for more
+    rsm contacts [list]
+    rsm contacts search <query>
+    rsm contacts import <file.vcf|file.csv>
+    rsm contacts add <user_id|@username> [first name] [last name] [--share-phone]
+    rsm contacts remove <user_id|@username>...
+    rsm contacts resolve <@username>            look up a public chat, channel or user
+    rsm secret [list]                           list secret chats and their state
+    rsm secret new <user_id|@username>          start a secret chat
+    rsm secret close <chat>
+    rsm secret key <chat>                       show the encryption key to compare with the other side
+    rsm secret ttl <chat> <30s|5m|1h|1d|1w|off> set the self-destruct timer for new messages
+    rsm notify [--keywords <word,word>] [--mentions] [--quiet <from hour>-<to hour>]
+               [--sink bell|desktop[:<bus address>]|file:<path>|command:<shell command>]
+                                                notify about incoming messages, following chat notification settings
+    rsm export <chat> [--format json|markdown|html] [--since <yyyy-mm-dd>] [--until <yyyy-mm-dd>]
+                      [--output <path>] [--media]   save the history, with --media also attached files
+    rsm archive sync [chat]                     archive history not archived yet, of one chat or all of them
+    rsm archive watch                           archive every message as it arrives, which chats, chat, thread
+                                                and notify do too while they run
+    rsm grep <query> [--chat <title|id>] [--limit <n>]
+                                                search the archive offline
+    rsm database key [--remove]                 encrypt the local databases with a new key, or decrypt them
+    rsm option <name> [value] [--reset]         show or set a TDLib option, --reset sets it back to the default
+    rsm version                                 versions of rsm and TDLib, and since when you're logged in
+    rsm proxy [list]
+    rsm proxy add <link> [--disabled]           add a tg://proxy, tg://socks, t.me/proxy, t.me/socks, socks5://,
+                                                http:// or mtproto:// proxy, enabling it unless --disabled
+    rsm proxy enable|remove <proxy_id>
+    rsm proxy disable                           connect directly
+    rsm proxy ping [proxy_id]                   measure latency through one proxy, or all and directly
+
+    --databases files,chats,messages|none       which local databases to keep, all by default
+    --timeout <seconds>                         how long to wait for Telegram to answer, 60 by default, 0 for ever
+    --rate-limits <class=rate[/burst],...>      requests per second for read, write and files requests, and how many
+                                                can go at once, read=20/40,write=3/10,files=4/8 by default
+    --td-log                                    write TDLib's debug log to ~/.config/rust-messenger/td.log
+    --td-log-verbosity <0-5>                    0 fatal errors only, the default, up to 5 everything
+    --td-log-tags <tag=level,...>               verbosity for single parts of TDLib, like net=4
+    --td-log-file <path> [--td-log-max-size <MB>]
+                                                log to a file instead of stderr, rotated at 10 MB by default
+    RSM_DATABASE_KEY                            key the local databases are encrypted with
+    ~/.config/rust-messenger/proxies            proxy links, one per line, added on start with the first enabled
+    RSM_TD_LOG_VERBOSITY, RSM_TD_LOG_TAGS, RSM_TD_LOG_FILE, RSM_TD_LOG_MAX_SIZE
+                                                the --td-log-* options, for when they're always the same
+    RSM_RATE_LIMITS                             the --rate-limits option";
+
+pub struct Args {
+    pub command: Option<String>,
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Args {
+        let mut args = args.into_iter().peekable();
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut switches = Vec::new();
+
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if let Some((name, value)) = name.split_once('=') {
+                    options.insert(name.to_owned(), value.to_owned());
+                } else if SWITCHES.contains(&name) {
+                    switches.push(name.to_owned());
+                } else if let Some(value) = args.next_if(|next| !next.starts_with("--")) {
+                    options.insert(name.to_owned(), value);
+                } else {
+                    switches.push(name.to_owned());
+                }
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        let command = if positional.is_empty() {
+            None
+        } else {
+            Some(positional.remove(0))
+        };
+
+        Args {
+            command,
+            positional,
+            options,
+            switches,
+        }
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(|it| it.as_str())
+    }
+
+    /// Positional arguments starting at `index`, joined with spaces. Handy for unquoted message text.
+    pub fn rest(&self, index: usize) -> String {
+        self.positional.get(index..).unwrap_or_default().join(" ")
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|it| it.as_str())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.switches.iter().any(|it| it == name)
+    }
+
+    /// Positional argument parsed as a number, exiting with usage on failure.
+    pub fn id(&self, index: usize, what: &str) -> i64 {
+        self.positional(index)
+            .and_then(|it| it.parse::<i64>().ok())
+            .unwrap_or_else(|| exit_with_usage(&format!("expected numeric {}", what)))
+    }
+
+    /// Positional argument resolved as a chat, see `resolve_chat`.
+    pub async fn chat(&self, manager: &Manager, index: usize) -> i64 {
+        let target = self
+            .positional(index)
+            .unwrap_or_else(|| exit_with_usage("no chat given"));
+        resolve_chat(manager, target).await
+    }
+}
+
+/// Resolves a chat given by id, title, `@username` or link. When that's ambiguous the user is asked to pick one,
+/// or, if stdin isn't a terminal, the candidates are listed and rsm exits.
+pub async fn resolve_chat(manager: &Manager, target: &str) -> i64 {
+    let mut chats = manager.resolve_chat(target).await.unwrap_or_else(|error| exit_with_error(error));
+
+    match chats.len() {
+        0 => exit_with_usage(&format!("no chat matches '{}'", target)),
+        1 => return chats.remove(0).id,
+        _ => {}
+    }
+
+    let candidates = chats
+        .iter()
+        .enumerate()
+        .map(|(index, chat)| {
+            let secret = if let ChatType::ChatTypeSecret { .. } = chat.chat_type { "🔒 " } else { "" };
+            format!("{:>3}. {}{} #{}", index + 1, secret, chat.title, chat.id)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+        eprintln!("'{}' is ambiguous, it matches:\n{}", target, candidates);
+        std::process::exit(2)
+    }
+
+    eprintln!("'{}' matches several chats:\n{}", target, candidates);
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        eprint!("Which one? ");
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => std::process::exit(2),
+        };
+
+        match line.trim().parse::<usize>() {
+            Ok(choice) if (1..=chats.len()).contains(&choice) => return chats[choice - 1].id,
+            _ => eprintln!("Enter a number between 1 and {}", chats.len()),
+        }
+    }
+}
+
+/// Seconds in a duration like `30s`, `5m`, `12h`, `7d` or `1w`. None if it isn't one, or too long to count in an i32.
+pub fn duration(value: &str) -> Option<i32> {
+    // The unit is the last character, which needn't be ASCII if mistyped
+    let (unit_start, _) = value.char_indices().last()?;
+    let (number, unit) = value.split_at(unit_start);
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    number.parse::<i32>().ok().filter(|it| *it >= 0)?.checked_mul(unit_seconds)
+}
+
+pub fn exit_with_usage(error: &str) -> ! {
+    eprintln!("{}\n{}", error, USAGE);
+    std::process::exit(2)
+}
+
+/// For requests a command can't go on without, like getting the chat it's about.
+pub fn exit_with_error(error: Error) -> ! {
+    eprintln!("{} ({})", error.message, error.code);
+    std::process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration("30s"), Some(30));
+        assert_eq!(duration("5m"), Some(5 * 60));
+        assert_eq!(duration("12h"), Some(12 * 60 * 60));
+        assert_eq!(duration("7d"), Some(7 * 24 * 60 * 60));
+        assert_eq!(duration("2w"), Some(2 * 7 * 24 * 60 * 60));
+        assert_eq!(duration("0m"), Some(0));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        assert_eq!(duration(""), None);
+        assert_eq!(duration("m"), None);
+        assert_eq!(duration("30"), None);
+        assert_eq!(duration("5м"), None);
+        assert_eq!(duration("-5m"), None);
+        assert_eq!(duration("1y"), None);
+        assert_eq!(duration("3551w"), None);
+        assert_eq!(duration("3550w"), Some(3550 * 7 * 24 * 60 * 60));
+    }
+}
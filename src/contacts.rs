@@ -0,0 +1,392 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{ChatType, Contact, Error, User},
+    manager::Manager,
+    scheduler::Priority,
+};
+use crate::view::{self, PrettyPrint};
+use colored::Colorize;
+use std::path::Path;
+
+/// `rsm contacts <subcommand> ...`
+pub async fn run(manager: &Manager, args: &Args) {
+    let result = match args.positional(0) {
+        None | Some("list") => list(manager).await,
+        Some("search") => search(manager, args).await,
+        Some("import") => import(manager, args).await,
+        Some("add") => add(manager, args).await,
+        Some("remove") => remove(manager, args).await,
+        Some("resolve") => resolve(manager, args).await,
+        Some(other) => cli::exit_with_usage(&format!("unknown contacts command '{}'", other)),
+    };
+
+    result.unwrap_or_else(|error| cli::exit_with_error(error));
+}
+
+/// A user given either as a numeric id or as an `@username`.
+pub async fn user_id(manager: &Manager, arg: &str) -> i64 {
+    if let Ok(id) = arg.parse::<i64>() {
+        return id;
+    }
+
+    match manager.search_public_chat(arg).await.map(|chat| chat.chat_type) {
+        Some(ChatType::ChatTypePrivate { user_id }) => user_id,
+        Some(_) => cli::exit_with_usage(&format!("'{}' is not a user", arg)),
+        None => cli::exit_with_usage(&format!("no user named '{}'", arg)),
+    }
+}
+
+fn print_user(user: &User) {
+    let username = if user.username.is_empty() {
+        "".to_owned()
+    } else {
+        format!("@{} ", user.username)
+    };
+    let phone = if user.phone_number.is_empty() {
+        "".to_owned()
+    } else {
+        format!("+{} ", user.phone_number)
+    };
+
+    println!(
+        "{} {}{}{}{}",
+        view::full_name(user).bold(),
+        username.blue(),
+        phone,
+        format!("#{} ", user.id).bright_black(),
+        user.status.pretty_format().bright_black()
+    );
+}
+
+async fn print_users(manager: &Manager, user_ids: Vec<i64>) {
+    let mut users = Vec::new();
+    for id in user_ids {
+        users.push(manager.get_user(id).await.unwrap_or_else(|error| cli::exit_with_error(error)));
+    }
+    users.sort_by_key(view::full_name);
+
+    for user in &users {
+        print_user(user);
+    }
+}
+
+async fn list(manager: &Manager) -> Result<(), Error> {
+    print_users(manager, manager.get_contacts().await?.user_ids).await;
+    Ok(())
+}
+
+async fn search(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let query = args.rest(1);
+    print_users(manager, manager.search_contacts(&query, 50).await?.user_ids).await;
+    Ok(())
+}
+
+async fn add(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let arg = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no user given"));
+    let user = manager
+        .get_user(user_id(manager, arg).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    let contact = Contact {
+        first_name: args
+            .positional(2)
+            .map(str::to_owned)
+            .unwrap_or_else(|| user.first_name.clone()),
+        last_name: args.rest(3),
+        user_id: user.id,
+        ..Default::default()
+    };
+
+    manager.add_contact(contact, args.flag("share-phone")).await?;
+
+    // Show where to write to them, the private chat is created if there wasn't one yet
+    let chat = manager.create_private_chat(user.id).await?;
+    println!("{} {}", chat.title.bold(), format!("#{}", chat.id).bright_black());
+    Ok(())
+}
+
+async fn remove(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let mut user_ids = Vec::new();
+    let mut index = 1;
+    while let Some(arg) = args.positional(index) {
+        user_ids.push(user_id(manager, arg).await);
+        index += 1;
+    }
+
+    if user_ids.is_empty() {
+        cli::exit_with_usage("no users given");
+    }
+
+    manager.remove_contacts(user_ids).await
+}
+
+async fn resolve(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let username = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no username given"));
+
+    match manager.search_public_chat(username).await {
+        Some(chat) => println!("{} {}", chat.title.bold(), format!("#{}", chat.id).bright_black()),
+        None => {
+            eprintln!("No chat named {}", username);
+            std::process::exit(1)
+        }
+    }
+
+    Ok(())
+}
+
+async fn import(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let manager = &manager.with_priority(Priority::Background);
+    let path = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no file given"));
+    let text = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("Can't read {}: {}", path, error);
+        std::process::exit(1)
+    });
+
+    let contacts = match Path::new(path).extension().and_then(|it| it.to_str()) {
+        Some("vcf") | Some("vcard") => parse_vcards(&text),
+        Some("csv") => parse_csv(&text),
+        _ => cli::exit_with_usage("expected a .vcf or .csv file"),
+    };
+
+    if contacts.is_empty() {
+        eprintln!("No contacts with phone numbers in {}", path);
+        std::process::exit(1)
+    }
+
+    let imported = manager.import_contacts(contacts.clone()).await?;
+
+    for (contact, user_id) in contacts.iter().zip(imported.user_ids) {
+        let name = format!("{} {}", contact.first_name, contact.last_name);
+        if user_id == 0 {
+            println!("{} {}", name.trim().bright_black(), "not on Telegram".bright_black());
+        } else {
+            println!("{} {}", name.trim().bold(), format!("#{}", user_id).bright_black());
+        }
+    }
+
+    Ok(())
+}
+
+fn split_name(name: &str) -> (String, String) {
+    match name.trim().split_once(' ') {
+        Some((first, last)) => (first.to_owned(), last.trim().to_owned()),
+        None => (name.trim().to_owned(), "".to_owned()),
+    }
+}
+
+fn unescape_vcard(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Contacts from a vCard file, one per phone number so nobody's second number gets lost.
+fn parse_vcards(text: &str) -> Vec<Contact> {
+    // Long lines are folded by starting the continuation with whitespace
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut contacts = Vec::new();
+    let mut name = (String::new(), String::new());
+    let mut phones = Vec::new();
+
+    for line in lines {
+        let (key, value) = match line.split_once(':') {
+            Some(it) => it,
+            None => continue,
+        };
+        // Properties may be grouped (`item1.TEL`) and carry parameters (`TEL;TYPE=CELL`)
+        let key = key.split(';').next().unwrap_or_default();
+        let key = key.rsplit('.').next().unwrap_or_default().to_uppercase();
+
+        match key.as_str() {
+            "BEGIN" => {
+                name = (String::new(), String::new());
+                phones.clear();
+            }
+            "N" => {
+                let mut parts = value.split(';').map(unescape_vcard);
+                let last = parts.next().unwrap_or_default();
+                let first = parts.next().unwrap_or_default();
+                if !first.is_empty() || !last.is_empty() {
+                    name = (first, last);
+                }
+            }
+            "FN" if name.0.is_empty() && name.1.is_empty() => name = split_name(&unescape_vcard(value)),
+            "TEL" => phones.push(value.trim().trim_start_matches("tel:").to_owned()),
+            "END" => {
+                for phone in phones.drain(..) {
+                    contacts.push(Contact {
+                        phone_number: phone,
+                        first_name: name.0.clone(),
+                        last_name: name.1.clone(),
+                        ..Default::default()
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    contacts
+}
+
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(char),
+        }
+    }
+    cells.push(cell);
+
+    cells.into_iter().map(|it| it.trim().to_owned()).collect()
+}
+
+/// Contacts from a CSV file. The header is matched against what common address books export,
+/// without a recognizable header the columns are taken to be first name, last name and phone.
+fn parse_csv(text: &str) -> Vec<Contact> {
+    let mut rows = text.lines().filter(|it| !it.trim().is_empty()).map(parse_csv_line).peekable();
+
+    let header: Vec<String> = rows
+        .peek()
+        .map(|it| it.iter().map(|cell| cell.to_lowercase()).collect())
+        .unwrap_or_default();
+    let column = |names: &[&str]| header.iter().position(|cell| names.contains(&cell.as_str()));
+
+    let first = column(&["first name", "first_name", "given name", "firstname"]);
+    let last = column(&["last name", "last_name", "family name", "lastname", "surname"]);
+    let full = column(&["name", "full name", "display name"]);
+    let phone = header
+        .iter()
+        .position(|cell| cell.contains("phone") && !cell.contains("type") && !cell.contains("label"));
+
+    let (first, last, full, phone) = if phone.is_some() {
+        rows.next();
+        (first, last, full, phone)
+    } else {
+        (Some(0), Some(1), None, Some(2))
+    };
+
+    let cell = |row: &Vec<String>, index: Option<usize>| {
+        index.and_then(|index| row.get(index)).cloned().unwrap_or_default()
+    };
+
+    rows.filter_map(|row| {
+        // Some exports put several numbers in one cell separated by " ::: "
+        let phone_number = cell(&row, phone).split(":::").next().unwrap_or_default().trim().to_owned();
+        if phone_number.is_empty() {
+            return None;
+        }
+
+        let (first_name, last_name) = if first.is_some() || last.is_some() {
+            (cell(&row, first), cell(&row, last))
+        } else {
+            split_name(&cell(&row, full))
+        };
+
+        Some(Contact {
+            phone_number,
+            first_name,
+            last_name,
+            ..Default::default()
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn people(contacts: &[Contact]) -> Vec<(&str, &str, &str)> {
+        contacts
+            .iter()
+            .map(|it| (it.first_name.as_str(), it.last_name.as_str(), it.phone_number.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn vcard_folded_lines_are_joined() {
+        let text = "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Lovelace;Ada;;;\r\nFN:Ada Love\r\n lace\r\nTEL;TYPE=CELL:+44 20\r\n\t7946 0000\r\nEND:VCARD\r\n";
+
+        assert_eq!(people(&parse_vcards(text)), vec![("Ada", "Lovelace", "+44 207946 0000")]);
+    }
+
+    #[test]
+    fn vcard_without_n_uses_fn() {
+        let text = "BEGIN:VCARD\nFN:Grace Brewster Hopper\nTEL:tel:+1-202-555-0100\nEND:VCARD\n";
+
+        assert_eq!(people(&parse_vcards(text)), vec![("Grace", "Brewster Hopper", "+1-202-555-0100")]);
+    }
+
+    #[test]
+    fn vcard_multiple_tel_entries_give_a_contact_each() {
+        let text = "BEGIN:VCARD\nN:Turing;Alan\nTEL;TYPE=HOME:+441\nitem1.TEL:+442\nEND:VCARD\n\
+                    BEGIN:VCARD\nN:Nobody;No\nEND:VCARD\n\
+                    BEGIN:VCARD\nN:Hamilton;Margaret\nTEL:+13\nEND:VCARD\n";
+
+        assert_eq!(
+            people(&parse_vcards(text)),
+            vec![("Alan", "Turing", "+441"), ("Alan", "Turing", "+442"), ("Margaret", "Hamilton", "+13")]
+        );
+    }
+
+    #[test]
+    fn csv_quoted_fields() {
+        assert_eq!(parse_csv_line(r#"a,"b, c","say ""hi""",  d "#), vec!["a", "b, c", r#"say "hi""#, "d"]);
+        assert_eq!(parse_csv_line(r#""",x,"#), vec!["", "x", ""]);
+    }
+
+    #[test]
+    fn csv_with_header() {
+        let text = "Given Name,Family Name,Phone 1 - Type,Phone 1 - Value\n\
+                    Ada,Lovelace,Mobile,+441 ::: +442\n\
+                    \n\
+                    Nobody,Here,Mobile,\n\
+                    \"Mary, Jr.\",\"O'Brien\",Work,\"+1 555\"\n";
+
+        assert_eq!(
+            people(&parse_csv(text)),
+            vec![("Ada", "Lovelace", "+441"), ("Mary, Jr.", "O'Brien", "+1 555")]
+        );
+    }
+
+    #[test]
+    fn csv_with_full_name_column() {
+        let text = "Name,Phone\nAlan Mathison Turing,+44\n";
+
+        assert_eq!(people(&parse_csv(text)), vec![("Alan", "Mathison Turing", "+44")]);
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let text = "Ada,Lovelace,+441\nAlan,Turing,+442\n";
+
+        assert_eq!(people(&parse_csv(text)), vec![("Ada", "Lovelace", "+441"), ("Alan", "Turing", "+442")]);
+    }
+}
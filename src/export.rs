@@ -0,0 +1,772 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{Chat, ChatType, Message, MessageContent, MessageSender, Poll},
+    manager::Manager,
+    scheduler::Priority,
+};
+use crate::view;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What Telegram Desktop writes instead of a path when media isn't exported.
+const NOT_INCLUDED: &str = "(File not included. Change data exporting settings to download.)";
+
+#[derive(Serialize)]
+struct ExportedPollAnswer {
+    text: String,
+    voters: i32,
+    chosen: bool,
+}
+
+#[derive(Serialize)]
+struct ExportedPoll {
+    question: String,
+    closed: bool,
+    total_voters: i32,
+    answers: Vec<ExportedPollAnswer>,
+}
+
+/// A message the way Telegram Desktop's `result.json` has it, as far as rsm models messages.
+#[derive(Serialize)]
+struct ExportedMessage {
+    id: i64,
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    date: String,
+    date_unixtime: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edited: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edited_unixtime: Option<String>,
+    from: String,
+    from_id: String,
+    /// What happened, for service messages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to_message_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    photo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poll: Option<ExportedPoll>,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct ExportedChat {
+    name: String,
+    #[serde(rename = "type")]
+    chat_type: &'static str,
+    id: i64,
+    messages: Vec<ExportedMessage>,
+}
+
+/// `--since`/`--until` as unixtime, both days included.
+fn date_bound(args: &Args, name: &str, end_of_day: bool) -> Option<i64> {
+    args.value(name).map(|value| {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .unwrap_or_else(|_| cli::exit_with_usage(&format!("--{} expects a date like 2022-10-31", name)));
+        let date = if end_of_day { date.succ() } else { date };
+
+        day_start(&Local, date)
+    })
+}
+
+/// Unixtime of midnight in `zone`, or of midnight UTC where local midnight falls in a DST gap.
+fn day_start<Tz: TimeZone>(zone: &Tz, date: NaiveDate) -> i64 {
+    let midnight = date.and_hms(0, 0, 0);
+
+    zone.from_local_datetime(&midnight)
+        .earliest()
+        .map(|it| it.timestamp())
+        .unwrap_or_else(|| midnight.timestamp())
+}
+
+/// CamelCase TDLib type name without the `Message` prefix in snake_case, e.g. `chat_add_members`.
+fn snake_case(content: &MessageContent) -> String {
+    let name = format!("{:?}", content);
+    let name = name.split(['(', ' ']).next().unwrap_or_default();
+    let name = name.strip_prefix("Message").unwrap_or(name);
+
+    let mut snake = String::new();
+    for (index, char) in name.chars().enumerate() {
+        if char.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.push(char.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn is_service(content: &MessageContent) -> bool {
+    matches!(
+        content,
+        MessageContent::MessageBasicGroupChatCreate
+            | MessageContent::MessageSupergroupChatCreate
+            | MessageContent::MessageChatAddMembers
+            | MessageContent::MessageChatChangePhoto
+            | MessageContent::MessageChatChangeTitle
+            | MessageContent::MessageChatDeleteMember
+            | MessageContent::MessageChatDeletePhoto
+            | MessageContent::MessageChatJoinByLink
+            | MessageContent::MessageChatJoinByRequest
+            | MessageContent::MessageChatSetTheme
+            | MessageContent::MessageChatSetTtl
+            | MessageContent::MessageChatUpgradeFrom
+            | MessageContent::MessageChatUpgradeTo
+            | MessageContent::MessageContactRegistered
+            | MessageContent::MessageCustomServiceAction
+            | MessageContent::MessagePinMessage
+            | MessageContent::MessageScreenshotTaken
+            | MessageContent::MessageVideoChatScheduled
+            | MessageContent::MessageVideoChatStarted
+            | MessageContent::MessageVideoChatEnded
+            | MessageContent::MessageInviteVideoChatParticipants
+            | MessageContent::MessageWebsiteConnected
+    )
+}
+
+fn text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::MessageText(body) => body.text.text.clone(),
+        MessageContent::MessageDocument(body) => body.caption.text.clone(),
+        MessageContent::MessagePhoto(body) => body.caption.text.clone(),
+        MessageContent::MessageVideo(body) => body.caption.text.clone(),
+        MessageContent::MessageAudio(body) => body.caption.text.clone(),
+        MessageContent::MessageVoiceNote(body) => body.caption.text.clone(),
+        _ => "".to_owned(),
+    }
+}
+
+fn poll(poll: &Poll) -> ExportedPoll {
+    ExportedPoll {
+        question: poll.question.clone(),
+        closed: poll.is_closed,
+        total_voters: poll.total_voter_count,
+        answers: poll
+            .options
+            .iter()
+            .map(|option| ExportedPollAnswer {
+                text: option.text.clone(),
+                voters: option.voter_count,
+                chosen: option.is_chosen,
+            })
+            .collect(),
+    }
+}
+
+fn format_date(unixtime: i32) -> String {
+    view::datetime(unixtime).format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+async fn chat_type(manager: &Manager, chat: &Chat) -> &'static str {
+    match chat.chat_type {
+        ChatType::ChatTypePrivate { .. } | ChatType::ChatTypeSecret { .. } => "personal_chat",
+        ChatType::ChatTypeBasicGroup { .. } => "private_group",
+        ChatType::ChatTypeSupergroup { supergroup_id, is_channel } => {
+            let supergroup = match manager.supergroup(supergroup_id) {
+                Some(supergroup) => Ok(supergroup),
+                None => manager.get_supergroup(supergroup_id).await,
+            };
+            let public = match supergroup {
+                Ok(supergroup) => !supergroup.username.is_empty(),
+                Err(error) => {
+                    eprintln!("Can't tell whether the chat is public, exporting it as private: {}", error.message);
+                    false
+                }
+            };
+            match (is_channel, public) {
+                (true, true) => "public_channel",
+                (true, false) => "private_channel",
+                (false, true) => "public_supergroup",
+                (false, false) => "private_supergroup",
+            }
+        }
+    }
+}
+
+/// Exports one chat, resolving senders once and copying media next to the output when asked to.
+struct Exporter<'a> {
+    manager: &'a Manager,
+    senders: HashMap<String, String>,
+    /// Directory media is copied to, and its name as seen from the output file
+    media: Option<(PathBuf, String)>,
+}
+
+impl<'a> Exporter<'a> {
+    async fn sender(&mut self, chat: &Chat, sender: &MessageSender) -> (String, String) {
+        let id = match sender {
+            MessageSender::MessageSenderUser(user) => format!("user{}", user.user_id),
+            MessageSender::MessageSenderChat(sender) => format!("channel{}", sender.chat_id),
+        };
+
+        if !self.senders.contains_key(&id) {
+            let name = match sender {
+                MessageSender::MessageSenderUser(user) => {
+                    self.manager.get_user(user.user_id).await.map(|user| view::full_name(&user))
+                }
+                MessageSender::MessageSenderChat(sender) if sender.chat_id == chat.id => Ok(chat.title.clone()),
+                MessageSender::MessageSenderChat(sender) => {
+                    self.manager.get_chat(sender.chat_id).await.map(|chat| chat.title)
+                }
+            };
+            // Still tells the senders apart, by id
+            let name = name.unwrap_or_else(|error| {
+                eprintln!("Can't get the name of {}: {}", id, error.message);
+                id.clone()
+            });
+            self.senders.insert(id.clone(), name);
+        }
+
+        (self.senders[&id].clone(), id)
+    }
+
+    /// Downloads the message's file and copies it into the media directory, returning its relative path.
+    async fn media_file(&self, message: &Message) -> Option<String> {
+        let (directory, relative) = self.media.as_ref()?;
+        let file = message.content.file()?;
+
+        let name = match &message.content {
+            MessageContent::MessageDocument(body) if !body.document.file_name.is_empty() => {
+                body.document.file_name.clone()
+            }
+            MessageContent::MessageVideo(body) if !body.video.file_name.is_empty() => body.video.file_name.clone(),
+            MessageContent::MessageAudio(body) if !body.audio.file_name.is_empty() => body.audio.file_name.clone(),
+            MessageContent::MessagePhoto(_) => "photo.jpg".to_owned(),
+            MessageContent::MessageVoiceNote(_) => "voice.ogg".to_owned(),
+            _ => format!("file_{}", file.id),
+        };
+        let name = format!("{}_{}", message.id, name.replace(['/', '\\'], "_"));
+
+        let file = match self.manager.download_file(file.id).await {
+            Ok(file) => file,
+            Err(error) => {
+                eprintln!("Can't download the file of message {}: {}", message.id, error.message);
+                return None;
+            }
+        };
+        if !file.local.is_downloading_completed {
+            eprintln!("Can't download the file of message {}", message.id);
+            return None;
+        }
+
+        match fs::copy(&file.local.path, directory.join(&name)) {
+            Ok(_) => Some(format!("{}/{}", relative, name)),
+            Err(error) => {
+                eprintln!("Can't copy {}: {}", file.local.path, error);
+                None
+            }
+        }
+    }
+
+    async fn message(&mut self, chat: &Chat, message: &Message) -> ExportedMessage {
+        let (from, from_id) = self.sender(chat, &message.sender_id).await;
+        let path = match message.content.file() {
+            Some(_) => Some(self.media_file(message).await.unwrap_or_else(|| NOT_INCLUDED.to_owned())),
+            None => None,
+        };
+
+        let mut exported = ExportedMessage {
+            id: message.id,
+            message_type: if is_service(&message.content) { "service" } else { "message" },
+            date: format_date(message.date),
+            date_unixtime: message.date.to_string(),
+            edited: Some(message.edit_date).filter(|it| *it > 0).map(format_date),
+            edited_unixtime: Some(message.edit_date).filter(|it| *it > 0).map(|it| it.to_string()),
+            from,
+            from_id,
+            action: None,
+            reply_to_message_id: Some(message.reply_to_message_id).filter(|it| *it != 0),
+            photo: None,
+            file: None,
+            media_type: None,
+            mime_type: None,
+            duration_seconds: None,
+            poll: None,
+            text: text(&message.content),
+        };
+
+        match &message.content {
+            MessageContent::MessageText(_) => {}
+            MessageContent::MessagePhoto(_) => exported.photo = path,
+            MessageContent::MessageDocument(body) => {
+                exported.file = path;
+                exported.mime_type = Some(body.document.mime_type.clone());
+            }
+            MessageContent::MessageVideo(body) => {
+                exported.file = path;
+                exported.media_type = Some("video_file".to_owned());
+                exported.mime_type = Some(body.video.mime_type.clone());
+                exported.duration_seconds = Some(body.video.duration);
+            }
+            MessageContent::MessageAudio(body) => {
+                exported.file = path;
+                exported.media_type = Some("audio_file".to_owned());
+                exported.mime_type = Some(body.audio.mime_type.clone());
+                exported.duration_seconds = Some(body.audio.duration);
+            }
+            MessageContent::MessageVoiceNote(body) => {
+                exported.file = path;
+                exported.media_type = Some("voice_message".to_owned());
+                exported.mime_type = Some(body.voice_note.mime_type.clone());
+                exported.duration_seconds = Some(body.voice_note.duration);
+            }
+            MessageContent::MessagePoll(body) => exported.poll = Some(poll(&body.poll)),
+            content if is_service(content) => exported.action = Some(snake_case(content)),
+            content => exported.media_type = Some(snake_case(content)),
+        }
+
+        exported
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn day(message: &ExportedMessage) -> &str {
+    message.date.split('T').next().unwrap_or_default()
+}
+
+fn time(message: &ExportedMessage) -> &str {
+    message.date.split('T').nth(1).map(|it| &it[..5]).unwrap_or_default()
+}
+
+fn markdown(chat: &ExportedChat) -> String {
+    let mut out = format!("# {}\n", chat.name);
+    let mut last_day = "";
+
+    for message in &chat.messages {
+        if day(message) != last_day {
+            last_day = day(message);
+            out.push_str(&format!("\n## {}\n", last_day));
+        }
+
+        if let Some(action) = &message.action {
+            out.push_str(&format!("\n_{} {} · {}_\n", message.from, action.replace('_', " "), time(message)));
+            continue;
+        }
+
+        let edited = if message.edited.is_some() { " · edited" } else { "" };
+        out.push_str(&format!("\n**{}** · {} · #{}{}\n", message.from, time(message), message.id, edited));
+
+        if let Some(id) = message.reply_to_message_id {
+            out.push_str(&format!("> ↪ #{}\n", id));
+        }
+        match &message.photo {
+            Some(photo) if photo == NOT_INCLUDED => out.push_str("_[photo]_\n"),
+            Some(photo) => out.push_str(&format!("![photo]({})\n", photo.replace(' ', "%20"))),
+            None => {}
+        }
+        match &message.file {
+            Some(file) if file == NOT_INCLUDED => {
+                let media_type = message.media_type.as_deref().unwrap_or("file");
+                out.push_str(&format!("_[{}]_\n", media_type.replace('_', " ")));
+            }
+            Some(file) => {
+                let name = file.rsplit('/').next().unwrap_or(file);
+                out.push_str(&format!("[{}]({})\n", name, file.replace(' ', "%20")));
+            }
+            None => {
+                if let Some(media_type) = &message.media_type {
+                    out.push_str(&format!("_[{}]_\n", media_type.replace('_', " ")));
+                }
+            }
+        }
+        if let Some(poll) = &message.poll {
+            out.push_str(&format!("📊 **{}**\n", poll.question));
+            for answer in &poll.answers {
+                let mark = if answer.chosen { "x" } else { " " };
+                out.push_str(&format!("- [{}] {} ({})\n", mark, answer.text, answer.voters));
+            }
+        }
+        if !message.text.is_empty() {
+            out.push_str(&message.text);
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+fn html(chat: &ExportedChat) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; max-width: 48em; margin: 2em auto; }}\n\
+         .day {{ text-align: center; color: #888; margin: 1.5em 0 0.5em; }}\n\
+         .message {{ margin: 0.8em 0; }}\n\
+         .meta {{ color: #888; font-size: 0.85em; }}\n\
+         .reply {{ border-left: 3px solid #ccc; padding-left: 0.5em; color: #888; }}\n\
+         .service {{ text-align: center; color: #888; font-style: italic; }}\n\
+         .text {{ white-space: pre-wrap; }}\n\
+         img {{ max-width: 100%; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape_html(&chat.name),
+        escape_html(&chat.name)
+    );
+    let mut last_day = "";
+
+    for message in &chat.messages {
+        if day(message) != last_day {
+            last_day = day(message);
+            out.push_str(&format!("<div class=\"day\">{}</div>\n", last_day));
+        }
+
+        if let Some(action) = &message.action {
+            out.push_str(&format!(
+                "<div class=\"service\" id=\"message{}\">{} {}</div>\n",
+                message.id,
+                escape_html(&message.from),
+                action.replace('_', " ")
+            ));
+            continue;
+        }
+
+        let edited = if message.edited.is_some() { " · edited" } else { "" };
+        out.push_str(&format!(
+            "<div class=\"message\" id=\"message{}\">\n<div><b>{}</b> <span class=\"meta\">{} · #{}{}</span></div>\n",
+            message.id,
+            escape_html(&message.from),
+            time(message),
+            message.id,
+            edited
+        ));
+
+        if let Some(id) = message.reply_to_message_id {
+            out.push_str(&format!("<div class=\"reply\"><a href=\"#message{}\">↪ #{}</a></div>\n", id, id));
+        }
+        if let Some(photo) = message.photo.as_ref().filter(|it| *it != NOT_INCLUDED) {
+            out.push_str(&format!("<img src=\"{}\">\n", escape_html(photo)));
+        }
+        if let Some(file) = message.file.as_ref().filter(|it| *it != NOT_INCLUDED) {
+            let name = file.rsplit('/').next().unwrap_or(file);
+            out.push_str(&format!("<div><a href=\"{}\">{}</a></div>\n", escape_html(file), escape_html(name)));
+        }
+        if let Some(media_type) = &message.media_type {
+            out.push_str(&format!("<div class=\"meta\">[{}]</div>\n", media_type.replace('_', " ")));
+        }
+        if let Some(poll) = &message.poll {
+            out.push_str(&format!("<div>📊 <b>{}</b></div>\n<ul>\n", escape_html(&poll.question)));
+            for answer in &poll.answers {
+                let mark = if answer.chosen { " ✓" } else { "" };
+                out.push_str(&format!(
+                    "<li>{} <span class=\"meta\">({}){}</span></li>\n",
+                    escape_html(&answer.text),
+                    answer.voters,
+                    mark
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+        if !message.text.is_empty() {
+            out.push_str(&format!("<div class=\"text\">{}</div>\n", escape_html(&message.text)));
+        }
+        out.push_str("</div>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// `rsm export <chat> [--format json|markdown|html] [--since <date>] [--until <date>] [--output <path>] [--media]`
+pub async fn run(manager: &Manager, args: &Args) {
+    let manager = &manager.with_priority(Priority::Background);
+    let chat = manager
+        .get_chat(args.chat(manager, 0).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+    let since = date_bound(args, "since", false);
+    let until = date_bound(args, "until", true);
+
+    let extension = match args.value("format").unwrap_or("json") {
+        "json" => "json",
+        "markdown" | "md" => "md",
+        "html" => "html",
+        other => cli::exit_with_usage(&format!("unknown export format '{}'", other)),
+    };
+
+    let output = match args.value("output") {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let name: String = chat
+                .title
+                .chars()
+                .map(|it| if it.is_alphanumeric() || it == '-' { it } else { '_' })
+                .collect();
+            PathBuf::from(format!("{}.{}", name, extension))
+        }
+    };
+
+    let media = if args.flag("media") {
+        let stem = output
+            .file_stem()
+            .and_then(|it| it.to_str())
+            .unwrap_or("export")
+            .to_owned();
+        let relative = format!("{}_files", stem);
+        let directory = output.parent().unwrap_or_else(|| Path::new("")).join(&relative);
+        fs::create_dir_all(&directory).unwrap_or_else(|error| {
+            eprintln!("Can't create {}: {}", directory.display(), error);
+            std::process::exit(1)
+        });
+        Some((directory, relative))
+    } else {
+        None
+    };
+
+    // History comes newest first, page back until before `since` or the beginning of the chat
+    let mut history = Vec::new();
+    let mut from_message_id = 0;
+    'paging: loop {
+        let page = manager
+            .get_chat_history(chat.id, from_message_id, 100)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+        if page.is_empty() {
+            break;
+        }
+        from_message_id = page.last().map(|message| message.id).unwrap_or_default();
+
+        for message in page {
+            if since.map(|since| (message.date as i64) < since).unwrap_or(false) {
+                break 'paging;
+            }
+            if until.map(|until| (message.date as i64) < until).unwrap_or(true) {
+                history.push(message);
+            }
+        }
+        eprint!("\rFetched {} messages", history.len());
+    }
+    eprintln!();
+    history.reverse();
+
+    let mut exporter = Exporter {
+        manager,
+        senders: HashMap::new(),
+        media,
+    };
+    let mut messages = Vec::new();
+    for message in &history {
+        messages.push(exporter.message(&chat, message).await);
+    }
+
+    let exported = ExportedChat {
+        name: chat.title.clone(),
+        chat_type: chat_type(manager, &chat).await,
+        id: chat.id,
+        messages,
+    };
+
+    let contents = match extension {
+        "md" => markdown(&exported),
+        "html" => html(&exported),
+        _ => serde_json::to_string_pretty(&exported).expect("Can't serialize export"),
+    };
+
+    fs::write(&output, contents).unwrap_or_else(|error| {
+        eprintln!("Can't write {}: {}", output.display(), error);
+        std::process::exit(1)
+    });
+    println!("Exported {} messages to {}", exported.messages.len(), output.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::fixtures;
+    use chrono::{FixedOffset, LocalResult, NaiveDateTime};
+    use serde_json::json;
+
+    /// UTC+1 where the clocks skip the first hour of every day, so midnight never happens.
+    #[derive(Clone)]
+    struct MidnightGap;
+
+    impl TimeZone for MidnightGap {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> MidnightGap {
+            MidnightGap
+        }
+
+        fn offset_from_local_date(&self, _: &NaiveDate) -> LocalResult<FixedOffset> {
+            LocalResult::Single(FixedOffset::east(3600))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            match local.time().format("%H").to_string().as_str() {
+                "00" => LocalResult::None,
+                _ => LocalResult::Single(FixedOffset::east(3600)),
+            }
+        }
+
+        fn offset_from_utc_date(&self, _: &NaiveDate) -> FixedOffset {
+            FixedOffset::east(3600)
+        }
+
+        fn offset_from_utc_datetime(&self, _: &NaiveDateTime) -> FixedOffset {
+            FixedOffset::east(3600)
+        }
+    }
+
+    fn content(value: serde_json::Value) -> MessageContent {
+        serde_json::from_value(value).expect("Can't build content")
+    }
+
+    fn exported(id: i64, date: &str, text: &str) -> ExportedMessage {
+        ExportedMessage {
+            id,
+            message_type: "message",
+            date: date.to_owned(),
+            date_unixtime: "0".to_owned(),
+            edited: None,
+            edited_unixtime: None,
+            from: "Ada".to_owned(),
+            from_id: "user2".to_owned(),
+            action: None,
+            reply_to_message_id: None,
+            photo: None,
+            file: None,
+            media_type: None,
+            mime_type: None,
+            duration_seconds: None,
+            poll: None,
+            text: text.to_owned(),
+        }
+    }
+
+    fn exported_chat() -> ExportedChat {
+        ExportedChat {
+            name: "Ada & Bob".to_owned(),
+            chat_type: "personal_chat",
+            id: 1,
+            messages: vec![
+                exported(1, "2022-10-30T09:05:00", "hello"),
+                ExportedMessage {
+                    message_type: "service",
+                    action: Some("chat_add_members".to_owned()),
+                    ..exported(2, "2022-10-31T10:00:00", "")
+                },
+                ExportedMessage {
+                    edited: Some("2022-10-31T10:02:00".to_owned()),
+                    reply_to_message_id: Some(1),
+                    photo: Some(NOT_INCLUDED.to_owned()),
+                    ..exported(3, "2022-10-31T10:01:00", "see <this>")
+                },
+                ExportedMessage {
+                    poll: Some(ExportedPoll {
+                        question: "Lunch?".to_owned(),
+                        closed: false,
+                        total_voters: 3,
+                        answers: vec![
+                            ExportedPollAnswer { text: "Yes".to_owned(), voters: 2, chosen: true },
+                            ExportedPollAnswer { text: "No".to_owned(), voters: 1, chosen: false },
+                        ],
+                    }),
+                    ..exported(4, "2022-10-31T10:03:00", "")
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn snake_case_drops_the_message_prefix() {
+        assert_eq!(snake_case(&content(json!({ "@type": "messageChatAddMembers" }))), "chat_add_members");
+        assert_eq!(snake_case(&content(json!({ "@type": "messageSticker" }))), "sticker");
+        assert_eq!(snake_case(&content(json!({ "@type": "messageText", "text": { "text": "hi" } }))), "text");
+    }
+
+    #[test]
+    fn date_bounds_include_both_days() {
+        let args = Args::parse(vec!["--since=2022-06-15".to_owned(), "--until=2022-06-15".to_owned()]);
+        let since = date_bound(&args, "since", false).unwrap();
+        let until = date_bound(&args, "until", true).unwrap();
+
+        assert_eq!(until - since, 24 * 60 * 60);
+        assert_eq!(date_bound(&args, "after", false), None);
+    }
+
+    #[test]
+    fn day_start_falls_back_to_utc_in_a_dst_gap() {
+        let date = NaiveDate::from_ymd(2022, 6, 15);
+
+        assert_eq!(day_start(&FixedOffset::east(3600), date), 1655247600);
+        assert_eq!(day_start(&MidnightGap, date), 1655251200);
+    }
+
+    #[tokio::test]
+    async fn message_maps_service_messages_and_media() {
+        let manager = Manager::new();
+        let mut exporter = Exporter {
+            manager: &manager,
+            senders: HashMap::from([("user2".to_owned(), "Ada".to_owned())]),
+            media: None,
+        };
+        let chat = fixtures::chat(json!({ "@type": "chatTypePrivate", "user_id": 2 }));
+
+        let text = exporter.message(&chat, &fixtures::message(5, "hi")).await;
+        assert_eq!(
+            (text.message_type, text.from.as_str(), text.from_id.as_str(), text.text.as_str()),
+            ("message", "Ada", "user2", "hi")
+        );
+        assert_eq!((text.date_unixtime.as_str(), text.action, text.media_type), ("5", None, None));
+
+        let service = Message {
+            content: content(json!({ "@type": "messageChatAddMembers", "member_user_ids": [3] })),
+            ..fixtures::message(6, "")
+        };
+        let service = exporter.message(&chat, &service).await;
+        assert_eq!(service.message_type, "service");
+        assert_eq!(service.action.as_deref(), Some("chat_add_members"));
+        assert_eq!(service.media_type, None);
+
+        let sticker = Message {
+            content: content(json!({ "@type": "messageSticker" })),
+            ..fixtures::message(7, "")
+        };
+        let sticker = exporter.message(&chat, &sticker).await;
+        assert_eq!(sticker.message_type, "message");
+        assert_eq!(sticker.action, None);
+        assert_eq!(sticker.media_type.as_deref(), Some("sticker"));
+    }
+
+    #[test]
+    fn markdown_groups_messages_by_day() {
+        assert_eq!(
+            markdown(&exported_chat()),
+            "# Ada & Bob\n\
+             \n## 2022-10-30\n\
+             \n**Ada** · 09:05 · #1\nhello\n\
+             \n## 2022-10-31\n\
+             \n_Ada chat add members · 10:00_\n\
+             \n**Ada** · 10:01 · #3 · edited\n> ↪ #1\n_[photo]_\nsee <this>\n\
+             \n**Ada** · 10:03 · #4\n📊 **Lunch?**\n- [x] Yes (2)\n- [ ] No (1)\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_and_links_replies() {
+        let html = html(&exported_chat());
+
+        assert!(html.contains("<title>Ada &amp; Bob</title>"));
+        assert!(html.contains("<div class=\"day\">2022-10-30</div>\n<div class=\"message\" id=\"message1\">"));
+        assert!(html.contains("<div class=\"service\" id=\"message2\">Ada chat add members</div>"));
+        assert!(html.contains("<div class=\"reply\"><a href=\"#message1\">↪ #1</a></div>"));
+        assert!(html.contains("<div class=\"text\">see &lt;this&gt;</div>"));
+        assert!(html.contains("<li>Yes <span class=\"meta\">(2) ✓</span></li>"));
+        assert!(!html.contains("<img"));
+    }
+}
@@ -1,53 +1,52 @@
+mod admin;
+#[cfg(feature = "archive")]
+mod archive;
+mod cli;
+mod contacts;
+mod export;
+mod notify;
+mod proxy;
+mod secret;
 mod telegram;
+mod view;
 
 #[macro_use]
 extern crate dotenv_codegen;
 
-use chrono::prelude::DateTime;
-use chrono::Local;
-use colored::Colorize;
 use dirs;
 use dotenv::dotenv;
-use futures::stream::{self, StreamExt};
-use std::time::{Duration, UNIX_EPOCH};
+use std::time::Duration;
 use telegram::{
-    api::{Chat, ChatType, MessageSender, User, UserStatus},
+    api::{
+        ChatAction, Error, Message, MessageContent, MessageSender, MessageSenderUser, OptionValue, OptionValueBoolean,
+        OptionValueInteger, OptionValueString, PollType, ReactionType, SearchMessagesFilter,
+    },
+    log::TdLog,
     manager::*,
+    scheduler::Limits,
 };
+use view::PrettyPrint;
 
-trait PrettyPrint {
-    fn pretty_format(&self) -> String;
-}
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
 
-impl PrettyPrint for DateTime<Local> {
-    fn pretty_format(&self) -> String {
-        let now: DateTime<Local> = Local::now();
+    let args = cli::Args::parse(std::env::args().skip(1));
 
-        if self.date() == now.date() {
-            self.format("%H:%M ").to_string()
-        } else {
-            self.format("%m.%d ").to_string()
-        }
+    // Searching the archive is offline and shouldn't wait for Telegram
+    if args.command.as_deref() == Some("grep") {
+        #[cfg(feature = "archive")]
+        return archive::grep(&args);
+        #[cfg(not(feature = "archive"))]
+        cli::exit_with_usage("rsm was built without the archive feature");
     }
-}
 
-impl PrettyPrint for UserStatus {
-    fn pretty_format(&self) -> String {
-        match self {
-            UserStatus::UserStatusEmpty | UserStatus::UserStatusOffline => "".to_owned(),
-            UserStatus::UserStatusLastMonth => "(month) ".to_owned(),
-            UserStatus::UserStatusLastWeek => "(week) ".to_owned(),
-            UserStatus::UserStatusOnline => "(online) ".to_owned(),
-            UserStatus::UserStatusRecently => "(recently) ".to_owned(),
-        }
+    if let Err(error) = td_log(&args).apply() {
+        eprintln!("Can't set up TDLib log: {}", error.message);
+        std::process::exit(1);
     }
-}
 
-#[tokio::main]
-async fn main() {
-    dotenv().ok();
-
-    let manager = Manager::new();
+    let manager = Manager::new().with_timeout(timeout(&args)).with_rate_limits(rate_limits(&args));
     manager
         .authorize(
             &format!(
@@ -59,102 +58,645 @@ async fn main() {
                     .expect("Can't convert home directory to string")
             ),
             "Terminal",
+            &databases(&args),
+            &proxy::configured(),
         )
         .await;
 
-    let chat_ids = manager.get_chats(40).await.chat_ids;
+    let command = async {
+        match args.command.as_deref() {
+            None | Some("chats") => view::chat_list(&manager).await,
+            Some("chat") => view::conversation(&manager, args.chat(&manager, 0).await).await,
+            Some("thread") => view::thread(&manager, args.chat(&manager, 0).await, args.id(1, "message id")).await,
+            Some("send") => send(&manager, &args).await,
+            Some("edit") => edit(&manager, &args).await,
+            Some("delete") => delete(&manager, &args).await,
+            Some("forward") => forward(&manager, &args).await,
+            Some("search") => search(&manager, &args).await,
+            Some("read") => read(&manager, &args).await,
+            Some("react") => react(&manager, &args).await,
+            Some("reactions") => reactions(&manager, &args).await,
+            Some("poll") => poll(&manager, &args).await,
+            Some("vote") => vote(&manager, &args).await,
+            Some("stop-poll") => manager
+                .stop_poll(args.chat(&manager, 0).await, args.id(1, "message id"))
+                .await
+                .unwrap_or_else(|error| cli::exit_with_error(error)),
+            Some("unread") => manager
+                .toggle_chat_is_marked_as_unread(args.chat(&manager, 0).await, true)
+                .await
+                .unwrap_or_else(|error| cli::exit_with_error(error)),
+            Some("admin") => admin::run(&manager, &args).await,
+            Some("contacts") => contacts::run(&manager, &args).await,
+            Some("secret") => secret::run(&manager, &args).await,
+            Some("notify") => notify::run(&manager, &args).await,
+            Some("export") => export::run(&manager, &args).await,
+            Some("database") => database(&manager, &args).await,
+            Some("option") => option(&manager, &args).await,
+            Some("version") => version(&manager),
+            Some("proxy") => proxy::run(&manager, &args).await,
+            #[cfg(feature = "archive")]
+            Some("archive") => archive::run(&manager, &args).await,
+            #[cfg(not(feature = "archive"))]
+            Some("archive") => cli::exit_with_usage("rsm was built without the archive feature"),
+            Some(command) => cli::exit_with_usage(&format!("unknown command '{}'", command)),
+        }
+    };
+    #[cfg(feature = "archive")]
+    let command = archive::alongside(&manager, &args, command);
+    command.await;
 
-    let chats = stream::iter(chat_ids)
-        .then(|id| manager.get_chat(id))
-        .collect::<Vec<Chat>>()
-        .await;
+    // Going through many chats or members runs into rate limits, say how much they slowed things down
+    let rate_limits = manager.rate_limits();
+    if rate_limits.flood_waits > 0 || rate_limits.retries > 0 {
+        eprintln!(
+            "Rate limited {} times: {} retries waiting {}s in total and at most {}s at once, {} requests failed",
+            rate_limits.flood_waits,
+            rate_limits.retries,
+            rate_limits.total_wait.as_secs(),
+            rate_limits.longest_wait.as_secs(),
+            rate_limits.failures
+        );
+    }
+}
+
+/// `--databases files,chats,messages|none` picks TDLib's local databases, all of them by default so chats and
+/// history are there offline. `RSM_DATABASE_KEY` encrypts them.
+fn databases(args: &cli::Args) -> Databases {
+    let mut databases = Databases {
+        encryption_key: std::env::var("RSM_DATABASE_KEY").unwrap_or_default(),
+        ..Default::default()
+    };
+
+    if let Some(list) = args.value("databases") {
+        let list: Vec<&str> = list.split(',').map(str::trim).collect();
+        if let Some(unknown) = list.iter().find(|it| !["files", "chats", "messages", "none"].contains(it)) {
+            cli::exit_with_usage(&format!("unknown database '{}'", unknown));
+        }
+
+        databases.files = list.contains(&"files");
+        databases.chat_info = list.contains(&"chats");
+        databases.messages = list.contains(&"messages");
+    }
+
+    databases
+}
+
+/// `--td-log` writes TDLib's debug log to ~/.config/rust-messenger/td.log, `--td-log-verbosity`, `--td-log-tags`,
+/// `--td-log-file` and `--td-log-max-size` set it up in detail. Each can also be set as `RSM_TD_LOG_VERBOSITY` etc.
+fn td_log(args: &cli::Args) -> TdLog {
+    let setting = |name: &str| {
+        args.value(name)
+            .map(str::to_owned)
+            .or_else(|| std::env::var(format!("RSM_{}", name.to_uppercase().replace('-', "_"))).ok())
+    };
+    let mut log = TdLog::default();
+
+    if args.flag("td-log") {
+        log.verbosity = 4;
+        log.file = Some(
+            dirs::home_dir()
+                .expect("Can't get home directory")
+                .join(".config/rust-messenger/td.log")
+                .to_str()
+                .expect("Can't convert home directory to string")
+                .to_owned(),
+        );
+    }
+    if let Some(verbosity) = setting("td-log-verbosity") {
+        log.verbosity = verbosity
+            .parse()
+            .unwrap_or_else(|_| cli::exit_with_usage("expected numeric log verbosity"));
+    }
+    if let Some(path) = setting("td-log-file") {
+        log.file = Some(path);
+    }
+    if let Some(megabytes) = setting("td-log-max-size") {
+        log.max_file_size = megabytes
+            .parse::<i64>()
+            .unwrap_or_else(|_| cli::exit_with_usage("expected log size in megabytes"))
+            * 1024
+            * 1024;
+    }
+    if let Some(tags) = setting("td-log-tags") {
+        log.tags = tags
+            .split(',')
+            .map(|tag| match tag.trim().split_once('=').map(|(tag, level)| (tag, level.parse::<i32>())) {
+                Some((tag, Ok(level))) => (tag.to_owned(), level),
+                _ => cli::exit_with_usage("expected log tags like net=4,td_requests=3"),
+            })
+            .collect();
+    }
+
+    log
+}
+
+/// `--timeout <seconds>` for how long to wait for an answer to each request, 0 to wait as long as it takes.
+fn timeout(args: &cli::Args) -> Option<Duration> {
+    match args.value("timeout").map(|it| it.parse::<u64>()) {
+        None => Some(DEFAULT_TIMEOUT),
+        Some(Ok(0)) => None,
+        Some(Ok(seconds)) => Some(Duration::from_secs(seconds)),
+        Some(Err(_)) => cli::exit_with_usage("expected timeout in seconds"),
+    }
+}
+
+/// `--rate-limits`, or `RSM_RATE_LIMITS` like the `--td-log-*` options.
+fn rate_limits(args: &cli::Args) -> Limits {
+    match args.value("rate-limits").map(str::to_owned).or_else(|| std::env::var("RSM_RATE_LIMITS").ok()) {
+        None => Limits::default(),
+        Some(value) => Limits::parse(&value)
+            .unwrap_or_else(|| cli::exit_with_usage("expected rate limits like read=20/40,write=3/10,files=4/8")),
+    }
+}
+
+/// `rsm option <name> [value] [--reset]`
+async fn option(manager: &Manager, args: &cli::Args) {
+    let name = args.positional(0).unwrap_or_else(|| cli::exit_with_usage("no option name given"));
 
-    let user_ids = chats
-        .iter()
-        .map(|chat| {
-            if let telegram::api::MessageSender::MessageSenderUser(user) =
-                &chat.last_message.sender_id
-            {
-                Some(user.user_id)
-            } else {
-                None
+    let value = match args.positional(1) {
+        None if !args.flag("reset") => {
+            let value = manager.get_option(name).await.unwrap_or_else(|error| cli::exit_with_error(error));
+            match value {
+                OptionValue::OptionValueBoolean(option) => println!("{}", option.value),
+                OptionValue::OptionValueInteger(option) => println!("{}", option.value),
+                OptionValue::OptionValueString(option) => println!("{}", option.value),
+                OptionValue::OptionValueEmpty => println!("not set"),
             }
-        })
-        .flat_map(|user| user)
-        .collect::<Vec<i64>>();
+            return;
+        }
+        None => OptionValue::OptionValueEmpty,
+        Some(value) => option_value(manager, name, value),
+    };
 
-    let users = stream::iter(user_ids)
-        .then(|id| manager.get_user(id))
-        .collect::<Vec<User>>()
-        .await;
+    manager
+        .set_option(name, value)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+}
 
-    // println!("{:?}", chats);
-    // println!("{:?}", users);
+/// Reads a value of the type TDLib last reported for the option, or one going by what it looks like for options that
+/// haven't been reported.
+fn option_value(manager: &Manager, name: &str, value: &str) -> OptionValue {
+    let boolean = || value.parse().map(|value| OptionValue::OptionValueBoolean(OptionValueBoolean { value }));
+    let integer = || value.parse().map(|value| OptionValue::OptionValueInteger(OptionValueInteger { value }));
+    let string = || OptionValue::OptionValueString(OptionValueString { value: value.to_owned() });
 
-    for chat in chats.iter().rev() {
-        let title = format!("{} ", chat.title);
+    if manager.option_bool(name).is_some() {
+        boolean().unwrap_or_else(|_| cli::exit_with_usage("expected true or false"))
+    } else if manager.option_integer(name).is_some() {
+        integer().unwrap_or_else(|_| cli::exit_with_usage("expected an integer"))
+    } else if manager.option_string(name).is_some() {
+        string()
+    } else {
+        boolean().or_else(|_| integer()).unwrap_or_else(|_| string())
+    }
+}
 
-        let unread_count = if chat.unread_count > 0 {
-            format!("({}+) ", chat.unread_count)
-        } else {
-            "".to_string()
-        };
+/// `rsm version`, of rsm and TDLib, and since when this session is logged in.
+fn version(manager: &Manager) {
+    println!(
+        "rsm {}, TDLib {}",
+        env!("CARGO_PKG_VERSION"),
+        manager.version().unwrap_or_else(|| "unknown".to_owned())
+    );
+    if let Some(date) = manager.authorization_date() {
+        println!("logged in since {}", view::datetime(date).format("%Y-%m-%d %H:%M"));
+    }
+}
 
-        let outgoing = if chat.last_message.is_outgoing {
-            "🖊  "
-        } else {
-            "📖  "
-        };
+/// `rsm database key [--remove]`
+async fn database(manager: &Manager, args: &cli::Args) {
+    match args.positional(0) {
+        Some("key") if args.flag("remove") => manager
+            .set_database_encryption_key("")
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error)),
+        Some("key") => {
+            let key = input_hidden("new database key >");
+            if key.is_empty() || input_hidden("repeat it >") != key {
+                eprintln!("The keys don't match, nothing changed");
+                std::process::exit(1)
+            }
 
-        let user = if let MessageSender::MessageSenderUser(id) = &chat.last_message.sender_id {
-            match chat.chat_type {
-                ChatType::ChatTypeBasicGroup | ChatType::ChatTypeSupergroup => {
-                    let user = users.iter().find(|user| user.id == id.user_id).expect("Can't find user info");
-                    format!(
-                        "{} ",
-                        format!("{} {}", user.first_name, user.last_name).trim()
-                    )
-                }
+            manager
+                .set_database_encryption_key(&key)
+                .await
+                .unwrap_or_else(|error| cli::exit_with_error(error));
+            println!("Set RSM_DATABASE_KEY to the new key from now on");
+        }
+        Some(other) => cli::exit_with_usage(&format!("unknown database command '{}'", other)),
+        None => cli::exit_with_usage("no database command given"),
+    }
+}
+
+fn upload_action(content: &MessageContent) -> ChatAction {
+    match content {
+        MessageContent::MessagePhoto(_) => ChatAction::ChatActionUploadingPhoto { progress: 0 },
+        MessageContent::MessageVideo(_) => ChatAction::ChatActionUploadingVideo { progress: 0 },
+        MessageContent::MessageVoiceNote(_) => ChatAction::ChatActionUploadingVoiceNote { progress: 0 },
+        _ => ChatAction::ChatActionUploadingDocument { progress: 0 },
+    }
+}
+
+async fn send(manager: &Manager, args: &cli::Args) {
+    let mut chat_id = args.chat(manager, 0).await;
+    let text = args.rest(1);
+    let mut message_thread_id = 0;
 
-                _ => "".to_string(),
+    if let Some(id) = args.value("thread") {
+        let id = id.parse::<i64>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric thread message id"));
+        let info = manager.get_message_thread(chat_id, id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+        chat_id = info.chat_id;
+        message_thread_id = info.message_thread_id;
+    }
+
+    let reply_to_message_id = match args.value("reply") {
+        Some(id) => {
+            let id = id.parse::<i64>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric reply message id"));
+            let message = manager.get_message(chat_id, id).await.unwrap_or_else(|error| {
+                cli::exit_with_usage(&format!("can't reply to message {}: {}", id, error.message))
+            });
+            if message_thread_id == 0 {
+                message_thread_id = message.message_thread_id;
             }
-        } else {
-            "".to_string()
-        };
+            id
+        }
+        None => 0,
+    };
+
+    let message = if let Some(path) = args.value("file") {
+        let caption_length_max = manager.message_caption_length_max();
+        if text.encode_utf16().count() > caption_length_max {
+            cli::exit_with_usage(&format!("captions can't be longer than {} characters", caption_length_max));
+        }
+
+        let message = manager
+            .send_reply(
+                chat_id,
+                message_thread_id,
+                reply_to_message_id,
+                Manager::file_content(path, &text, args.value("thumbnail")),
+            )
+            .await
+            .unwrap_or_else(|error| send_failed(error));
+
+        if let Some(file) = message.content.file() {
+            let uploading = manager.start_chat_action(chat_id, upload_action(&message.content));
+
+            manager
+                .wait_for_upload(file.id, |file| {
+                    let percent = if file.size > 0 {
+                        file.remote.uploaded_size * 100 / file.size
+                    } else {
+                        0
+                    };
+                    print!("\rUploading {}: {}%", path, percent);
+                    let _ = std::io::Write::flush(&mut std::io::stdout());
+                })
+                .await;
+            uploading.stop().await;
+            println!();
+        }
+
+        message
+    } else {
+        let text = if text.is_empty() { compose(manager, chat_id).await } else { text };
+        if text.trim().is_empty() {
+            cli::exit_with_usage("nothing to send")
+        }
+
+        // Text longer than a message can hold goes as several messages, in order, the first one being the reply
+        let mut parts = Manager::split_text(&text, manager.message_text_length_max());
+        let last = parts.pop().expect("Text isn't empty");
+        let mut reply_to_message_id = reply_to_message_id;
+
+        for part in parts {
+            let message = manager
+                .send_reply(chat_id, message_thread_id, reply_to_message_id, Manager::text_content(&part))
+                .await
+                .unwrap_or_else(|error| send_failed(error));
+            wait_until_sent(manager, &message).await;
+            reply_to_message_id = 0;
+        }
+
+        manager
+            .send_reply(chat_id, message_thread_id, reply_to_message_id, Manager::text_content(&last))
+            .await
+            .unwrap_or_else(|error| send_failed(error))
+    };
+
+    wait_until_sent(manager, &message).await;
+}
+
+fn read_stdin() -> String {
+    let mut text = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut text).expect("Can't read from stdin");
+    text
+}
 
-        let user_status = if let MessageSender::MessageSenderUser(id) = &chat.last_message.sender_id
-        {
-            match chat.chat_type {
-                ChatType::ChatTypePrivate => {
-                    let user = users.iter().find(|user| user.id == id.user_id).expect("Can't find user info");
-                    user.status.pretty_format()
+/// Reads the message from stdin, showing the chat that the user is typing while it's typed on the terminal.
+async fn compose(manager: &Manager, chat_id: i64) -> String {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+        return read_stdin();
+    }
+
+    eprintln!("Type the message, end it with Ctrl-D");
+    let typing = manager.start_chat_action(chat_id, ChatAction::ChatActionTyping);
+    let text = tokio::task::spawn_blocking(read_stdin).await.expect("Can't read from stdin");
+    typing.stop().await;
+
+    text
+}
+
+fn send_failed(error: Error) -> ! {
+    eprintln!("Can't send message: {} ({})", error.message, error.code);
+    std::process::exit(1)
+}
+
+async fn wait_until_sent(manager: &Manager, message: &Message) {
+    if let Err(error) = manager.wait_until_sent(message).await {
+        send_failed(error)
+    }
+}
+
+async fn edit(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let message_id = args.id(1, "message id");
+    let text = args.rest(2);
+
+    if text.is_empty() {
+        cli::exit_with_usage("new text is empty");
+    }
+
+    manager
+        .edit_message_text(chat_id, message_id, &text)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+}
+
+fn message_ids(args: &cli::Args, from: usize) -> Vec<i64> {
+    let ids = (from..)
+        .map_while(|index| args.positional(index))
+        .map(|id| id.parse::<i64>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric message ids")))
+        .collect::<Vec<i64>>();
+
+    if ids.is_empty() {
+        cli::exit_with_usage("no message ids given");
+    }
+
+    ids
+}
+
+async fn delete(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let message_ids = message_ids(args, 1);
+    let everyone = args.flag("everyone");
+
+    if everyone {
+        for &id in &message_ids {
+            let message = manager.get_message(chat_id, id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+            if !message.can_be_deleted_for_all_users {
+                eprintln!("Message {} can only be deleted for yourself", id);
+            }
+        }
+    }
+
+    manager
+        .delete_messages(chat_id, message_ids, everyone)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+}
+
+async fn forward(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let from_chat_id = args.chat(manager, 1).await;
+    let message_ids = message_ids(args, 2);
+
+    let forwarded = manager
+        .forward_messages(
+            chat_id,
+            from_chat_id,
+            message_ids.clone(),
+            args.flag("copy"),
+            args.flag("no-caption"),
+        )
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    for (id, message) in message_ids.iter().zip(forwarded) {
+        match message {
+            Some(message) => {
+                if let Err(error) = manager.wait_until_sent(&message).await {
+                    eprintln!("Can't forward message {}: {} ({})", id, error.message, error.code);
                 }
+            }
+            None => eprintln!("Message {} can't be forwarded", id),
+        }
+    }
+}
 
-                _ => "".to_string(),
+fn search_filter(name: Option<&str>) -> SearchMessagesFilter {
+    match name {
+        None => SearchMessagesFilter::SearchMessagesFilterEmpty,
+        Some("documents") => SearchMessagesFilter::SearchMessagesFilterDocument,
+        Some("photos") => SearchMessagesFilter::SearchMessagesFilterPhoto,
+        Some("videos") => SearchMessagesFilter::SearchMessagesFilterVideo,
+        Some("media") => SearchMessagesFilter::SearchMessagesFilterPhotoAndVideo,
+        Some("audio") => SearchMessagesFilter::SearchMessagesFilterAudio,
+        Some("voice") => SearchMessagesFilter::SearchMessagesFilterVoiceNote,
+        Some("gifs") => SearchMessagesFilter::SearchMessagesFilterAnimation,
+        Some("links") => SearchMessagesFilter::SearchMessagesFilterUrl,
+        Some("mentions") => SearchMessagesFilter::SearchMessagesFilterMention,
+        Some("pinned") => SearchMessagesFilter::SearchMessagesFilterPinned,
+        Some(other) => cli::exit_with_usage(&format!("unknown filter '{}'", other)),
+    }
+}
+
+async fn search(manager: &Manager, args: &cli::Args) {
+    let query = args.rest(0);
+    let filter = search_filter(args.value("filter"));
+    let limit = args
+        .value("limit")
+        .map(|it| it.parse::<usize>().unwrap_or_else(|_| cli::exit_with_usage("expected numeric limit")))
+        .unwrap_or(20);
+    let sender = args.value("from").map(|id| {
+        MessageSender::MessageSenderUser(MessageSenderUser {
+            user_id: id.parse().unwrap_or_else(|_| cli::exit_with_usage("expected numeric sender id")),
+        })
+    });
+    let chat_id = match args.value("chat") {
+        Some(target) => Some(cli::resolve_chat(manager, target).await),
+        None => None,
+    };
+
+    // Searching all chats can't be narrowed down by sender
+    if sender.is_some() && chat_id.is_none() {
+        cli::exit_with_usage("--from only works together with --chat");
+    }
+    if query.is_empty() && args.value("filter").is_none() && sender.is_none() {
+        cli::exit_with_usage("nothing to search for");
+    }
+
+    let mut found = Vec::new();
+    while found.len() < limit {
+        let page_size = (limit - found.len()).min(100) as i32;
+
+        let page = match chat_id {
+            Some(chat_id) => {
+                let from_message_id = found.last().map(|it: &Message| it.id).unwrap_or_default();
+                manager
+                    .search_chat_messages(chat_id, &query, sender.clone(), filter.clone(), from_message_id, page_size)
+                    .await
             }
-        } else {
-            "".to_string()
+            None => manager.search_messages(&query, filter.clone(), found.last(), page_size).await,
         };
+        let page = page.unwrap_or_else(|error| cli::exit_with_error(error));
 
-        println!(
-            "{}{}{}{}{}",
-            title.bold().blue(),
-            user.bright_black(),
-            outgoing.bold().bright_black(),
-            user_status.bold().blue(),
-            unread_count.bold().magenta()
-        );
+        if page.is_empty() {
+            break;
+        }
+        found.extend(page);
+    }
 
-        let body = match &chat.last_message.content {
-            telegram::api::MessageContent::MessageText(body) => format!("{}", body.text.text),
-            it @ _ => format!("{:?}", it).underline().to_string(),
-        };
+    view::search_results(manager, &found).await;
+}
+
+async fn read(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let chat = manager.get_chat(chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+
+    if let Some(message) = &chat.last_message {
+        manager
+            .view_messages(chat_id, 0, vec![message.id], true)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+    }
+
+    if chat.is_marked_as_unread {
+        manager
+            .toggle_chat_is_marked_as_unread(chat_id, false)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+    }
+
+    if chat.unread_mention_count > 0 {
+        manager.read_all_chat_mentions(chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+    }
+
+    if chat.unread_reaction_count > 0 {
+        manager.read_all_chat_reactions(chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+    }
+}
 
-        let epoch = UNIX_EPOCH + Duration::from_secs(chat.last_message.date.try_into().expect("Can't convert unixtime to Duration"));
-        let datetime = DateTime::<Local>::from(epoch).pretty_format();
+async fn react(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let message_id = args.id(1, "message id");
+    let reaction = ReactionType::ReactionTypeEmoji {
+        emoji: args
+            .positional(2)
+            .unwrap_or_else(|| cli::exit_with_usage("no reaction given"))
+            .to_owned(),
+    };
 
-        println!("{}{}\n", datetime.bold().bright_black(), body);
+    if args.flag("remove") {
+        manager
+            .remove_message_reaction(chat_id, message_id, reaction)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+    } else {
+        let available = manager
+            .get_chat_available_reactions(chat_id)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+        if !available.contains(&reaction) {
+            eprintln!("This reaction isn't available in the chat, see `rsm reactions {}`", chat_id);
+            std::process::exit(1);
+        }
+
+        manager
+            .set_message_reaction(chat_id, message_id, reaction, args.flag("big"))
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+    }
+}
+
+async fn reactions(manager: &Manager, args: &cli::Args) {
+    let reactions = manager
+        .get_chat_available_reactions(args.chat(manager, 0).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    println!(
+        "{}",
+        reactions
+            .iter()
+            .map(PrettyPrint::pretty_format)
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+}
+
+async fn poll(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let question = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no poll question given"));
+    let options = (2..)
+        .map_while(|index| args.positional(index))
+        .map(|option| option.to_owned())
+        .collect::<Vec<String>>();
+
+    if options.len() < 2 {
+        cli::exit_with_usage("a poll needs at least two options");
+    }
+
+    let poll_type = match args.value("quiz") {
+        Some(correct) => PollType::PollTypeQuiz {
+            correct_option_id: correct
+                .parse::<i32>()
+                .ok()
+                .filter(|it| (1..=options.len() as i32).contains(it))
+                .unwrap_or_else(|| cli::exit_with_usage("--quiz expects the number of the correct option"))
+                - 1,
+            explanation: None,
+        },
+        None => PollType::PollTypeRegular {
+            allow_multiple_answers: args.flag("multiple"),
+        },
+    };
+
+    let message = manager
+        .send_message(
+            chat_id,
+            Manager::poll_content(question, options, !args.flag("public"), poll_type),
+        )
+        .await;
+
+    if let Err(error) = match message {
+        Ok(message) => manager.wait_until_sent(&message).await,
+        Err(error) => Err(error),
+    } {
+        eprintln!("Can't send poll: {} ({})", error.message, error.code);
+        std::process::exit(1);
     }
+}
+
+async fn vote(manager: &Manager, args: &cli::Args) {
+    let chat_id = args.chat(manager, 0).await;
+    let message_id = args.id(1, "message id");
 
-    loop {}
+    // Options are numbered from 1 on screen, TDLib counts from 0
+    let option_ids = (2..)
+        .map_while(|index| args.positional(index))
+        .map(|option| match option.parse::<i32>() {
+            Ok(number) if number > 0 => number - 1,
+            _ => cli::exit_with_usage("expected option numbers"),
+        })
+        .collect::<Vec<i32>>();
+
+    manager
+        .set_poll_answer(chat_id, message_id, option_ids)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
 }
@@ -0,0 +1,923 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{
+        Chat, ChatType, Error, Message, MessageContent, MessageSender, NotificationSettingsScope,
+        ScopeNotificationSettings, TgEventData,
+    },
+    manager::Manager,
+};
+use crate::view;
+use chrono::{Local, Timelike};
+use futures::StreamExt;
+use std::collections::{hash_map::Entry, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+
+/// A message worth telling the user about.
+#[derive(Clone)]
+pub struct Notification {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub title: String,
+    /// Sender and text, or only the sender when the chat hides message previews
+    pub body: String,
+}
+
+/// Where notifications end up.
+pub trait Sink {
+    fn notify(&mut self, notification: &Notification);
+
+    /// Called once everything in the chat was read, for sinks that keep notifications around.
+    fn clear(&mut self, _chat_id: i64) {}
+}
+
+enum SinkEvent {
+    Notify(Notification),
+    Clear(i64),
+}
+
+/// Passes notifications on to a sink running on a thread of its own, for sinks that block while they deliver,
+/// like running a command or calling over D-Bus. Notifications still arrive in order, but updates aren't held up.
+pub struct ThreadSink {
+    events: mpsc::Sender<SinkEvent>,
+}
+
+impl ThreadSink {
+    pub fn new(mut sink: impl Sink + Send + 'static) -> ThreadSink {
+        let (events, received) = mpsc::channel();
+
+        thread::spawn(move || {
+            for event in received {
+                match event {
+                    SinkEvent::Notify(notification) => sink.notify(&notification),
+                    SinkEvent::Clear(chat_id) => sink.clear(chat_id),
+                }
+            }
+        });
+
+        ThreadSink { events }
+    }
+}
+
+impl Sink for ThreadSink {
+    // Only fails once the thread is gone, which means the sink panicked and was reported already
+    fn notify(&mut self, notification: &Notification) {
+        self.events.send(SinkEvent::Notify(notification.clone())).unwrap_or_default();
+    }
+
+    fn clear(&mut self, chat_id: i64) {
+        self.events.send(SinkEvent::Clear(chat_id)).unwrap_or_default();
+    }
+}
+
+/// Rings the terminal bell and prints the notification.
+pub struct BellSink;
+
+impl Sink for BellSink {
+    fn notify(&mut self, notification: &Notification) {
+        println!("\x07{}: {}", notification.title, notification.body);
+    }
+}
+
+/// Appends a tab separated line per notification: time, chat id, message id, title and body.
+pub struct FileSink {
+    file: File,
+}
+
+impl FileSink {
+    pub fn new(path: &str) -> std::io::Result<FileSink> {
+        Ok(FileSink {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+        })
+    }
+}
+
+impl Sink for FileSink {
+    fn notify(&mut self, notification: &Notification) {
+        let clean = |text: &str| text.replace(['\t', '\n'], " ");
+
+        writeln!(
+            self.file,
+            "{}\t{}\t{}\t{}\t{}",
+            Local::now().to_rfc3339(),
+            notification.chat_id,
+            notification.message_id,
+            clean(&notification.title),
+            clean(&notification.body)
+        )
+        .unwrap_or_else(|error| eprintln!("Can't write notification: {}", error));
+    }
+}
+
+/// Runs a shell command per notification, which gets it in `RSM_CHAT_ID`, `RSM_MESSAGE_ID`, `RSM_TITLE`
+/// and `RSM_BODY`. Waits for the command, so best wrapped in a `ThreadSink`.
+pub struct CommandSink {
+    command: String,
+}
+
+impl CommandSink {
+    pub fn new(command: &str) -> CommandSink {
+        CommandSink {
+            command: command.to_owned(),
+        }
+    }
+}
+
+impl Sink for CommandSink {
+    fn notify(&mut self, notification: &Notification) {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("RSM_CHAT_ID", notification.chat_id.to_string())
+            .env("RSM_MESSAGE_ID", notification.message_id.to_string())
+            .env("RSM_TITLE", &notification.title)
+            .env("RSM_BODY", &notification.body)
+            .status();
+
+        match status {
+            Ok(status) if !status.success() => eprintln!("Notification command failed with {}", status),
+            Err(error) => eprintln!("Can't run notification command: {}", error),
+            _ => {}
+        }
+    }
+}
+
+/// A chat's notification on the desktop, with the last few messages it shows.
+struct DesktopNotification {
+    id: u32,
+    lines: Vec<String>,
+}
+
+/// Posts notifications through `org.freedesktop.Notifications` with `gdbus` from GLib. Each chat has a single
+/// notification that's replaced by every new message and closed once the chat is read. Waits for `gdbus`,
+/// so best wrapped in a `ThreadSink`.
+pub struct DesktopSink {
+    /// Bus to talk to instead of the session bus, e.g. a private one for testing
+    address: Option<String>,
+    chats: HashMap<i64, DesktopNotification>,
+}
+
+/// Quotes text for the GVariant text format `gdbus` parses its arguments with.
+fn gvariant_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Id of the notification in the `gdbus` reply to `Notify`, which looks like `(uint32 42,)`.
+fn notification_id(reply: &str) -> Option<u32> {
+    reply
+        .trim()
+        .strip_prefix("(uint32 ")?
+        .strip_suffix(",)")?
+        .parse::<u32>()
+        .ok()
+}
+
+/// Notification servers may interpret a subset of HTML in the body.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl DesktopSink {
+    const SHOWN_LINES: usize = 5;
+
+    pub fn new(address: Option<&str>) -> DesktopSink {
+        DesktopSink {
+            address: address.map(str::to_owned),
+            chats: HashMap::new(),
+        }
+    }
+
+    fn call(&self, method: &str, args: &[String]) -> Option<String> {
+        let mut command = Command::new("gdbus");
+        command.arg("call");
+        match &self.address {
+            Some(address) => command.args(["--address", address]),
+            None => command.arg("--session"),
+        };
+        command
+            .args(["--dest", "org.freedesktop.Notifications"])
+            .args(["--object-path", "/org/freedesktop/Notifications"])
+            .args(["--method", &format!("org.freedesktop.Notifications.{}", method)])
+            .arg("--")
+            .args(args);
+
+        match command.output() {
+            Ok(output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+            Ok(output) => {
+                eprintln!("Can't post notification: {}", String::from_utf8_lossy(&output.stderr).trim());
+                None
+            }
+            Err(error) => {
+                eprintln!("Can't run gdbus: {}", error);
+                None
+            }
+        }
+    }
+}
+
+impl Sink for DesktopSink {
+    fn notify(&mut self, notification: &Notification) {
+        let chat = self
+            .chats
+            .entry(notification.chat_id)
+            .or_insert(DesktopNotification { id: 0, lines: Vec::new() });
+        chat.lines.push(escape_markup(&notification.body));
+
+        let count = chat.lines.len();
+        let summary = if count > 1 {
+            format!("{} ({} new messages)", notification.title, count)
+        } else {
+            notification.title.clone()
+        };
+        let body = chat.lines[count.saturating_sub(Self::SHOWN_LINES)..].join("\n");
+        let replaces_id = chat.id;
+
+        let reply = self.call(
+            "Notify",
+            &[
+                gvariant_string("rsm"),
+                replaces_id.to_string(),
+                gvariant_string(""),
+                gvariant_string(&summary),
+                gvariant_string(&body),
+                "@as []".to_owned(),
+                "{'category': <'im.received'>}".to_owned(),
+                "-1".to_owned(),
+            ],
+        );
+
+        let id = reply.as_deref().and_then(notification_id);
+        if let (Some(id), Some(chat)) = (id, self.chats.get_mut(&notification.chat_id)) {
+            chat.id = id;
+        }
+    }
+
+    fn clear(&mut self, chat_id: i64) {
+        if let Some(chat) = self.chats.remove(&chat_id) {
+            if chat.id != 0 {
+                self.call("CloseNotification", &[chat.id.to_string()]);
+            }
+        }
+    }
+}
+
+/// Our own rules, applied on top of the notification settings TDLib keeps per chat and per scope.
+#[derive(Default)]
+pub struct Rules {
+    /// Messages containing any of these always notify, even in muted chats
+    pub keywords: Vec<String>,
+    /// Only notify about mentions and private chats
+    pub mention_only: bool,
+    /// Hours of the day, from inclusive to exclusive, without any notifications. May wrap around midnight
+    pub quiet_hours: Option<(u32, u32)>,
+}
+
+impl Rules {
+    fn is_quiet(&self, hour: u32) -> bool {
+        match self.quiet_hours {
+            Some((from, to)) if from <= to => from <= hour && hour < to,
+            Some((from, to)) => hour >= from || hour < to,
+            None => false,
+        }
+    }
+
+    fn matches_keyword(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.keywords.iter().any(|keyword| text.contains(&keyword.to_lowercase()))
+    }
+}
+
+fn scope(chat: &Chat) -> NotificationSettingsScope {
+    match chat.chat_type {
+        ChatType::ChatTypePrivate { .. } | ChatType::ChatTypeSecret { .. } => {
+            NotificationSettingsScope::NotificationSettingsScopePrivateChats
+        }
+        ChatType::ChatTypeSupergroup { is_channel: true, .. } => {
+            NotificationSettingsScope::NotificationSettingsScopeChannelChats
+        }
+        _ => NotificationSettingsScope::NotificationSettingsScopeGroupChats,
+    }
+}
+
+/// Message content as plain text, without the styling `PrettyPrint` adds for the terminal.
+fn plain_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::MessageText(body) => body.text.text.clone(),
+        MessageContent::MessageDocument(body) => format!("[{}] {}", body.document.file_name, body.caption.text),
+        MessageContent::MessagePhoto(body) => format!("[photo] {}", body.caption.text),
+        MessageContent::MessageVideo(body) => format!("[video] {}", body.caption.text),
+        MessageContent::MessageAudio(body) => format!("[{}] {}", body.audio.file_name, body.caption.text),
+        MessageContent::MessageVoiceNote(body) => format!("[voice] {}", body.caption.text),
+        MessageContent::MessagePoll(body) => format!("[poll] {}", body.poll.question),
+        _ => "[message]".to_owned(),
+    }
+}
+
+/// Turns new messages into notifications, following chat and scope notification settings and our own rules.
+pub struct Notifier {
+    rules: Rules,
+    sink: Box<dyn Sink>,
+    chats: HashMap<i64, Chat>,
+    scopes: HashMap<NotificationSettingsScope, ScopeNotificationSettings>,
+}
+
+impl Notifier {
+    pub fn new(rules: Rules, sink: Box<dyn Sink>) -> Notifier {
+        Notifier {
+            rules,
+            sink,
+            chats: HashMap::new(),
+            scopes: HashMap::new(),
+        }
+    }
+
+    /// Keeps notification settings up to date and passes new messages that should notify to the sink.
+    /// Fails when the chat of a message or the settings of its scope can't be fetched to check them.
+    pub async fn handle(&mut self, manager: &Manager, update: &TgEventData) -> Result<(), Error> {
+        match update {
+            TgEventData::UpdateChatNotificationSettings(update) => {
+                if let Some(chat) = self.chats.get_mut(&update.chat_id) {
+                    chat.notification_settings = update.notification_settings.clone();
+                }
+            }
+            TgEventData::UpdateScopeNotificationSettings(update) => {
+                self.scopes
+                    .insert(update.scope.clone(), update.notification_settings.clone());
+            }
+            TgEventData::UpdateChatReadInbox(update) if update.unread_count == 0 => self.sink.clear(update.chat_id),
+            TgEventData::UpdateNewMessage(update) if !update.message.is_outgoing => {
+                let message = &update.message;
+
+                let chat = match self.chats.entry(message.chat_id) {
+                    Entry::Occupied(entry) => entry.get().clone(),
+                    Entry::Vacant(entry) => entry.insert(manager.get_chat(message.chat_id).await?).clone(),
+                };
+                if let Entry::Vacant(entry) = self.scopes.entry(scope(&chat)) {
+                    entry.insert(manager.get_scope_notification_settings(scope(&chat)).await?);
+                }
+
+                if self.should_notify(&chat, message, Local::now().hour()) {
+                    let notification = self.notification(manager, &chat, message).await;
+                    self.sink.notify(&notification);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn should_notify(&self, chat: &Chat, message: &Message, hour: u32) -> bool {
+        if self.rules.is_quiet(hour) {
+            return false;
+        }
+
+        if self.rules.matches_keyword(&plain_text(&message.content)) {
+            return true;
+        }
+
+        let settings = &chat.notification_settings;
+        let default = self.scopes.get(&scope(chat)).cloned().unwrap_or_default();
+
+        let mute_for = if settings.use_default_mute_for {
+            default.mute_for
+        } else {
+            settings.mute_for
+        };
+        let mentions_disabled = if settings.use_default_disable_mention_notifications {
+            default.disable_mention_notifications
+        } else {
+            settings.disable_mention_notifications
+        };
+        let mention = message.contains_unread_mention && !mentions_disabled;
+
+        // Mentions get through muted chats unless mention notifications are disabled too, like in the official apps
+        if mute_for > 0 && !mention {
+            return false;
+        }
+
+        let private = scope(chat) == NotificationSettingsScope::NotificationSettingsScopePrivateChats;
+        !self.rules.mention_only || mention || private
+    }
+
+    async fn notification(&self, manager: &Manager, chat: &Chat, message: &Message) -> Notification {
+        let settings = &chat.notification_settings;
+        let show_preview = if settings.use_default_show_preview {
+            self.scopes
+                .get(&scope(chat))
+                .map(|scope| scope.show_preview)
+                .unwrap_or(true)
+        } else {
+            settings.show_preview
+        };
+
+        let sender = match &message.sender_id {
+            MessageSender::MessageSenderUser(user) => {
+                manager.get_user(user.user_id).await.map(|user| view::full_name(&user))
+            }
+            MessageSender::MessageSenderChat(sender) if sender.chat_id == chat.id => Ok(chat.title.clone()),
+            MessageSender::MessageSenderChat(sender) => manager.get_chat(sender.chat_id).await.map(|chat| chat.title),
+        };
+        // The message is worth a notification even without knowing who sent it
+        let sender = sender.unwrap_or_else(|error| {
+            eprintln!("Can't get sender of message {}: {}", message.id, error.message);
+            "Someone".to_owned()
+        });
+
+        let body = match (show_preview, sender == chat.title) {
+            (true, true) => plain_text(&message.content),
+            (true, false) => format!("{}: {}", sender, plain_text(&message.content)),
+            (false, true) => "New message".to_owned(),
+            (false, false) => format!("New message from {}", sender),
+        };
+
+        Notification {
+            chat_id: chat.id,
+            message_id: message.id,
+            title: chat.title.clone(),
+            body,
+        }
+    }
+}
+
+/// `--sink bell|desktop[:<bus address>]|file:<path>|command:<shell command>`, the bell by default.
+pub fn sink(args: &Args) -> Box<dyn Sink> {
+    match args.value("sink").map(|it| it.split_once(':').unwrap_or((it, ""))) {
+        None | Some(("bell", _)) => Box::new(BellSink),
+        Some(("desktop", "")) => Box::new(ThreadSink::new(DesktopSink::new(None))),
+        Some(("desktop", address)) => Box::new(ThreadSink::new(DesktopSink::new(Some(address)))),
+        Some(("file", path)) if !path.is_empty() => Box::new(FileSink::new(path).unwrap_or_else(|error| {
+            eprintln!("Can't open {}: {}", path, error);
+            std::process::exit(1)
+        })),
+        Some(("command", command)) if !command.is_empty() => Box::new(ThreadSink::new(CommandSink::new(command))),
+        Some(_) => cli::exit_with_usage("expected --sink bell, desktop, file:<path> or command:<command>"),
+    }
+}
+
+/// `--keywords word,word`, `--mentions` and `--quiet <from>-<to>` in hours.
+pub fn rules(args: &Args) -> Rules {
+    let keywords = args
+        .value("keywords")
+        .map(|value| {
+            value
+                .split(',')
+                .map(|it| it.trim().to_owned())
+                .filter(|it| !it.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let quiet_hours = args.value("quiet").map(|value| {
+        let hours = value
+            .split_once('-')
+            .map(|(from, to)| (from.parse::<u32>(), to.parse::<u32>()));
+        match hours {
+            Some((Ok(from), Ok(to))) if from < 24 && to < 24 => (from, to),
+            _ => cli::exit_with_usage("expected --quiet <from hour>-<to hour>, e.g. 23-7"),
+        }
+    });
+
+    Rules {
+        keywords,
+        mention_only: args.flag("mentions"),
+        quiet_hours,
+    }
+}
+
+/// `rsm notify`, notifies about incoming messages until interrupted.
+pub async fn run(manager: &Manager, args: &Args) {
+    let mut updates = manager.updates();
+    let mut notifier = Notifier::new(rules(args), sink(args));
+
+    let interrupt = tokio::signal::ctrl_c();
+    tokio::pin!(interrupt);
+
+    loop {
+        tokio::select! {
+            update = updates.next() => match update {
+                Some(update) => {
+                    if let Err(error) = notifier.handle(manager, &update).await {
+                        eprintln!("Can't check whether to notify: {}", error.message);
+                    }
+                }
+                None => break,
+            },
+            _ = &mut interrupt => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::{
+        api::{
+            ChatNotificationSettings, MessageSenderChat, UpdateChatNotificationSettings, UpdateChatReadInbox,
+            UpdateNewMessage,
+        },
+        fixtures,
+    };
+    use serde_json::json;
+
+    fn message(text: &str, contains_unread_mention: bool) -> Message {
+        Message {
+            contains_unread_mention,
+            ..fixtures::message(1, text)
+        }
+    }
+
+    fn chat(chat_type: serde_json::Value, notification_settings: ChatNotificationSettings) -> Chat {
+        Chat {
+            notification_settings,
+            ..fixtures::chat(chat_type)
+        }
+    }
+
+    fn group(notification_settings: ChatNotificationSettings) -> Chat {
+        chat(json!({ "@type": "chatTypeBasicGroup", "basic_group_id": 3 }), notification_settings)
+    }
+
+    fn private(notification_settings: ChatNotificationSettings) -> Chat {
+        chat(json!({ "@type": "chatTypePrivate", "user_id": 2 }), notification_settings)
+    }
+
+    fn muted() -> ChatNotificationSettings {
+        ChatNotificationSettings {
+            mute_for: 3600,
+            ..Default::default()
+        }
+    }
+
+    fn notifier(rules: Rules) -> Notifier {
+        Notifier::new(rules, Box::new(BellSink))
+    }
+
+    #[test]
+    fn gvariant_strings_are_quoted() {
+        assert_eq!(gvariant_string("rsm"), r#""rsm""#);
+        assert_eq!(gvariant_string(""), r#""""#);
+        assert_eq!(gvariant_string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(gvariant_string(r"C:\temp"), r#""C:\\temp""#);
+        assert_eq!(gvariant_string("one\ntwo"), r#""one\ntwo""#);
+        assert_eq!(gvariant_string("it's"), r#""it's""#);
+    }
+
+    #[test]
+    fn notification_ids_are_read_from_replies() {
+        assert_eq!(notification_id("(uint32 42,)\n"), Some(42));
+        assert_eq!(notification_id("(uint32 0,)"), Some(0));
+        assert_eq!(notification_id("()"), None);
+        assert_eq!(notification_id("(uint32 -1,)"), None);
+        assert_eq!(notification_id("(uint32 7, 'extra')"), None);
+    }
+
+    #[test]
+    fn markup_is_escaped() {
+        assert_eq!(escape_markup("<b>fish & chips</b>"), "&lt;b&gt;fish &amp; chips&lt;/b&gt;");
+    }
+
+    /// A bus of our own, with `dbus-test-tool` standing in for the notification server and answering every call,
+    /// and `dbus-monitor` printing what's sent to it.
+    struct PrivateBus {
+        address: String,
+        processes: Vec<std::process::Child>,
+        monitor: mpsc::Receiver<String>,
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            for process in &mut self.processes {
+                let _ = process.kill();
+                let _ = process.wait();
+            }
+        }
+    }
+
+    impl PrivateBus {
+        /// None when the D-Bus tools aren't installed or the bus doesn't come up.
+        fn start() -> Option<PrivateBus> {
+            use std::io::{BufRead, BufReader};
+            use std::process::Stdio;
+
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address=1"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let daemon_output = daemon.stdout.take();
+            let (lines, monitor) = mpsc::channel();
+            let mut bus = PrivateBus {
+                address: String::new(),
+                processes: vec![daemon],
+                monitor,
+            };
+            BufReader::new(daemon_output?).read_line(&mut bus.address).ok()?;
+            bus.address = bus.address.trim().to_owned();
+
+            let mut monitor = Command::new("dbus-monitor")
+                .args(["--address", &bus.address])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let monitor_output = monitor.stdout.take();
+            bus.processes.push(monitor);
+            let monitor_output = BufReader::new(monitor_output?);
+            thread::spawn(move || {
+                for line in monitor_output.lines().map_while(Result::ok) {
+                    if lines.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            let server = Command::new("dbus-test-tool")
+                .args(["echo", "--name=org.freedesktop.Notifications"])
+                .env("DBUS_SESSION_BUS_ADDRESS", &bus.address)
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            bus.processes.push(server);
+
+            // Ready once the server owns its name and the monitor has seen us asking about it
+            for _ in 0..50 {
+                let owner = Command::new("gdbus")
+                    .args(["call", "--address", &bus.address, "--dest", "org.freedesktop.DBus"])
+                    .args(["--object-path", "/org/freedesktop/DBus"])
+                    .args(["--method", "org.freedesktop.DBus.NameHasOwner", "org.freedesktop.Notifications"])
+                    .output()
+                    .ok()?;
+                if String::from_utf8_lossy(&owner.stdout).contains("true") && bus.seen("NameHasOwner").is_some() {
+                    return Some(bus);
+                }
+                thread::sleep(std::time::Duration::from_millis(100));
+            }
+
+            None
+        }
+
+        /// What the monitor printed up to the first line containing `until`.
+        fn seen(&self, until: &str) -> Option<String> {
+            let mut lines = Vec::new();
+            while let Ok(line) = self.monitor.recv_timeout(std::time::Duration::from_secs(1)) {
+                let done = line.contains(until);
+                lines.push(line);
+                if done {
+                    return Some(lines.join("\n"));
+                }
+            }
+            None
+        }
+    }
+
+    #[test]
+    fn desktop_notifications_reach_the_bus() {
+        let bus = match PrivateBus::start() {
+            Some(bus) => bus,
+            None => return eprintln!("Skipping, can't start a private D-Bus with a notification server"),
+        };
+        let mut sink = DesktopSink::new(Some(&bus.address));
+        let notification = |body: &str| Notification {
+            chat_id: 1,
+            message_id: 1,
+            title: r#"Ada "The Countess""#.to_owned(),
+            body: body.to_owned(),
+        };
+
+        sink.notify(&notification("Bob: say \"hi\"\nfrom C:\\ & <b>"));
+        sink.notify(&notification("Bob: again"));
+
+        // dbus-monitor prints strings as they are, without escaping
+        let first = bus.seen("int32 -1").expect("The first notification didn't reach the bus");
+        assert!(first.contains("member=Notify"));
+        assert!(first.contains(r#"string "Ada "The Countess"""#));
+        assert!(first.contains("string \"Bob: say \"hi\"\nfrom C:\\ &amp; &lt;b&gt;\""));
+
+        // Messages of the same chat are collected in one notification
+        let second = bus.seen("int32 -1").expect("The second notification didn't reach the bus");
+        assert!(second.contains(r#"string "Ada "The Countess" (2 new messages)""#));
+        assert!(second.contains("string \"Bob: say \"hi\"\nfrom C:\\ &amp; &lt;b&gt;\nBob: again\""));
+    }
+
+    #[test]
+    fn quiet_hours() {
+        let rules = Rules {
+            quiet_hours: Some((9, 17)),
+            ..Default::default()
+        };
+
+        assert!(!rules.is_quiet(8));
+        assert!(rules.is_quiet(9));
+        assert!(rules.is_quiet(16));
+        assert!(!rules.is_quiet(17));
+        assert!(!Rules::default().is_quiet(3));
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight() {
+        let rules = Rules {
+            quiet_hours: Some((23, 7)),
+            ..Default::default()
+        };
+
+        for hour in [23, 0, 3, 6] {
+            assert!(rules.is_quiet(hour), "{} should be quiet", hour);
+        }
+        for hour in [7, 12, 22] {
+            assert!(!rules.is_quiet(hour), "{} shouldn't be quiet", hour);
+        }
+    }
+
+    #[test]
+    fn quiet_hours_silence_keywords_too() {
+        let notifier = notifier(Rules {
+            keywords: vec!["deploy".to_owned()],
+            quiet_hours: Some((23, 7)),
+            ..Default::default()
+        });
+
+        assert!(!notifier.should_notify(&group(Default::default()), &message("deploy now", true), 2));
+        assert!(notifier.should_notify(&group(Default::default()), &message("deploy now", true), 12));
+    }
+
+    #[test]
+    fn muted_chats_only_notify_about_mentions() {
+        let notifier = notifier(Rules::default());
+
+        assert!(notifier.should_notify(&group(Default::default()), &message("hi", false), 12));
+        assert!(!notifier.should_notify(&group(muted()), &message("hi", false), 12));
+        assert!(notifier.should_notify(&group(muted()), &message("hi @me", true), 12));
+
+        let no_mentions = ChatNotificationSettings {
+            disable_mention_notifications: true,
+            ..muted()
+        };
+        assert!(!notifier.should_notify(&group(no_mentions), &message("hi @me", true), 12));
+    }
+
+    #[test]
+    fn default_settings_come_from_the_scope() {
+        let mut notifier = notifier(Rules::default());
+        let settings = ChatNotificationSettings {
+            use_default_mute_for: true,
+            use_default_disable_mention_notifications: true,
+            ..muted()
+        };
+
+        // Without scope settings known, nothing is muted
+        assert!(notifier.should_notify(&group(settings.clone()), &message("hi", false), 12));
+
+        notifier.scopes.insert(
+            NotificationSettingsScope::NotificationSettingsScopeGroupChats,
+            ScopeNotificationSettings {
+                mute_for: 3600,
+                show_preview: true,
+                disable_mention_notifications: false,
+            },
+        );
+        assert!(!notifier.should_notify(&group(settings.clone()), &message("hi", false), 12));
+        assert!(notifier.should_notify(&group(settings.clone()), &message("hi @me", true), 12));
+        // Private chats have a scope of their own
+        assert!(notifier.should_notify(&private(settings.clone()), &message("hi", false), 12));
+
+        notifier.scopes.insert(
+            NotificationSettingsScope::NotificationSettingsScopeGroupChats,
+            ScopeNotificationSettings {
+                mute_for: 3600,
+                show_preview: true,
+                disable_mention_notifications: true,
+            },
+        );
+        assert!(!notifier.should_notify(&group(settings), &message("hi @me", true), 12));
+
+        // The chat's own setting wins where it doesn't use the default
+        let unmuted = ChatNotificationSettings {
+            use_default_mute_for: false,
+            mute_for: 0,
+            ..Default::default()
+        };
+        assert!(notifier.should_notify(&group(unmuted), &message("hi", false), 12));
+    }
+
+    #[test]
+    fn keywords_override_muting() {
+        let notifier = notifier(Rules {
+            keywords: vec!["Outage".to_owned()],
+            mention_only: true,
+            ..Default::default()
+        });
+
+        assert!(notifier.rules.matches_keyword("big OUTAGE in eu"));
+        assert!(!notifier.rules.matches_keyword("all good"));
+        assert!(notifier.should_notify(&group(muted()), &message("outage!", false), 12));
+        assert!(!notifier.should_notify(&group(muted()), &message("lunch?", false), 12));
+    }
+
+    #[test]
+    fn mention_only_lets_private_chats_through() {
+        let notifier = notifier(Rules {
+            mention_only: true,
+            ..Default::default()
+        });
+
+        assert!(!notifier.should_notify(&group(Default::default()), &message("hi", false), 12));
+        assert!(notifier.should_notify(&group(Default::default()), &message("hi @me", true), 12));
+        assert!(notifier.should_notify(&private(Default::default()), &message("hi", false), 12));
+    }
+
+    /// Keeps what reaches it, to check what a `Notifier` passes on.
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        events: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl Sink for RecordingSink {
+        fn notify(&mut self, notification: &Notification) {
+            let event = format!("{}: {}", notification.title, notification.body);
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn clear(&mut self, chat_id: i64) {
+            self.events.lock().unwrap().push(format!("clear {}", chat_id));
+        }
+    }
+
+    #[test]
+    fn file_sink_writes_a_line_per_notification() {
+        let path = std::env::temp_dir().join(format!("rsm-notify-test-{}.tsv", std::process::id()));
+        let path = path.to_str().expect("Can't convert temporary path to string");
+
+        let mut sink = FileSink::new(path).expect("Can't open notification file");
+        sink.notify(&Notification {
+            chat_id: 1,
+            message_id: 2,
+            title: "Ada\tLovelace".to_owned(),
+            body: "first line\nsecond line".to_owned(),
+        });
+        sink.notify(&Notification {
+            chat_id: 3,
+            message_id: 4,
+            title: "Bob".to_owned(),
+            body: "hi".to_owned(),
+        });
+
+        let written = std::fs::read_to_string(path).expect("Can't read notification file");
+        std::fs::remove_file(path).unwrap_or_default();
+
+        let lines: Vec<Vec<&str>> = written.lines().map(|line| line.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][1..], ["1", "2", "Ada Lovelace", "first line second line"]);
+        assert_eq!(lines[1][1..], ["3", "4", "Bob", "hi"]);
+        assert!(chrono::DateTime::parse_from_rfc3339(lines[0][0]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn handle_notifies_follows_settings_and_clears() {
+        // Chat and scope are known and the message comes from the chat itself, so TDLib is never asked
+        let manager = Manager::new();
+        let sink = RecordingSink::default();
+        let mut notifier = Notifier::new(Rules::default(), Box::new(sink.clone()));
+        let shown = ChatNotificationSettings {
+            show_preview: true,
+            ..Default::default()
+        };
+        notifier.chats.insert(1, group(shown));
+        notifier.scopes.insert(
+            NotificationSettingsScope::NotificationSettingsScopeGroupChats,
+            Default::default(),
+        );
+        let new_message = |text: &str, is_outgoing: bool| {
+            let sender_id = MessageSender::MessageSenderChat(MessageSenderChat { chat_id: 1 });
+            TgEventData::UpdateNewMessage(UpdateNewMessage {
+                message: Message {
+                    is_outgoing,
+                    sender_id,
+                    ..message(text, false)
+                },
+            })
+        };
+
+        notifier.handle(&manager, &new_message("hi", false)).await.unwrap();
+        notifier.handle(&manager, &new_message("from me", true)).await.unwrap();
+
+        let muted = TgEventData::UpdateChatNotificationSettings(UpdateChatNotificationSettings {
+            chat_id: 1,
+            notification_settings: muted(),
+        });
+        notifier.handle(&manager, &muted).await.unwrap();
+        notifier.handle(&manager, &new_message("while muted", false)).await.unwrap();
+
+        let read = TgEventData::UpdateChatReadInbox(UpdateChatReadInbox {
+            chat_id: 1,
+            last_read_inbox_message_id: 1,
+            unread_count: 0,
+        });
+        notifier.handle(&manager, &read).await.unwrap();
+
+        assert_eq!(*sink.events.lock().unwrap(), vec!["Chat: hi", "clear 1"]);
+    }
+}
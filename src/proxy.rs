@@ -0,0 +1,116 @@
+use crate::cli::{self, Args};
+use crate::telegram::{
+    api::{Error, Proxy, ProxyType},
+    manager::{self, Manager, ProxyLink},
+};
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// One proxy link per line, `#` starts a comment. They're all added before logging in and the first one is enabled,
+/// unless another proxy is.
+fn config_path() -> PathBuf {
+    dirs::home_dir()
+        .expect("Can't get home directory")
+        .join(".config/rust-messenger/proxies")
+}
+
+/// Proxies from the config file, none if there's no file.
+pub fn configured() -> Vec<ProxyLink> {
+    let text = match std::fs::read_to_string(config_path()) {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let proxy = manager::parse_proxy_link(line);
+            if proxy.is_none() {
+                eprintln!("Skipping {} in {}, it's not a proxy link", line, config_path().display());
+            }
+            proxy
+        })
+        .collect()
+}
+
+/// `rsm proxy <subcommand> ...`
+pub async fn run(manager: &Manager, args: &Args) {
+    let result = match args.positional(0) {
+        None | Some("list") => list(manager).await,
+        Some("add") => add(manager, args).await,
+        Some("enable") => manager.enable_proxy(proxy_id(args)).await,
+        Some("disable") => manager.disable_proxy().await,
+        Some("remove") => manager.remove_proxy(proxy_id(args)).await,
+        Some("ping") => ping(manager, args).await,
+        Some(other) => cli::exit_with_usage(&format!("unknown proxy command '{}'", other)),
+    };
+    result.unwrap_or_else(|error| cli::exit_with_error(error));
+}
+
+fn proxy_id(args: &Args) -> i32 {
+    args.id(1, "proxy id")
+        .try_into()
+        .unwrap_or_else(|_| cli::exit_with_usage("expected a proxy id"))
+}
+
+fn describe(proxy: &Proxy) -> String {
+    let kind = match &proxy.proxy_type {
+        ProxyType::ProxyTypeSocks5 { username, .. } if !username.is_empty() => format!("socks5 as {}", username),
+        ProxyType::ProxyTypeSocks5 { .. } => "socks5".to_owned(),
+        ProxyType::ProxyTypeHttp { username, .. } if !username.is_empty() => format!("http as {}", username),
+        ProxyType::ProxyTypeHttp { .. } => "http".to_owned(),
+        ProxyType::ProxyTypeMtproto { .. } => "mtproto".to_owned(),
+    };
+
+    format!("{}:{} {}", proxy.server, proxy.port, kind.bright_black())
+}
+
+async fn list(manager: &Manager) -> Result<(), Error> {
+    for proxy in manager.get_proxies().await? {
+        let enabled = if proxy.is_enabled { "enabled".green() } else { "".normal() };
+        println!("{} {} {}", format!("#{}", proxy.id).bright_black(), describe(&proxy), enabled);
+    }
+    Ok(())
+}
+
+async fn add(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let link = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no proxy link given"));
+    let proxy = manager::parse_proxy_link(link)
+        .unwrap_or_else(|| cli::exit_with_usage(&format!("'{}' is not a proxy link", link)));
+
+    let proxy = manager.add_proxy(proxy, !args.flag("disabled")).await?;
+    println!("{} {}", format!("#{}", proxy.id).bright_black(), describe(&proxy));
+    Ok(())
+}
+
+/// Pings one proxy, or all of them and a direct connection to compare.
+async fn ping(manager: &Manager, args: &Args) -> Result<(), Error> {
+    let proxies = match args.positional(1) {
+        Some(_) => {
+            let proxy_id = proxy_id(args);
+            let proxies: Vec<Proxy> = manager.get_proxies().await?.into_iter().filter(|it| it.id == proxy_id).collect();
+            if proxies.is_empty() {
+                cli::exit_with_usage(&format!("there's no proxy #{}", proxy_id));
+            }
+            proxies
+        }
+        None => {
+            match manager.ping_proxy(0).await {
+                Ok(seconds) => println!("{} {:.0} ms", "direct".bright_black(), seconds * 1000.0),
+                Err(error) => println!("{} {}", "direct".bright_black(), error.message.red()),
+            }
+            manager.get_proxies().await?
+        }
+    };
+
+    for proxy in proxies {
+        match manager.ping_proxy(proxy.id).await {
+            Ok(seconds) => println!("{} {:.0} ms", describe(&proxy), seconds * 1000.0),
+            Err(error) => println!("{} {}", describe(&proxy), error.message.red()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::cli::{self, Args};
+use crate::contacts;
+use crate::telegram::{
+    api::{ChatType, SecretChatState},
+    manager::Manager,
+};
+use crate::view;
+use colored::Colorize;
+
+/// `rsm secret <subcommand> ...`, end-to-end encrypted chats.
+pub async fn run(manager: &Manager, args: &Args) {
+    match args.positional(0) {
+        None | Some("list") => list(manager).await,
+        Some("new") => new(manager, args).await,
+        Some("close") => {
+            let secret_chat_id = secret_chat_id(manager, args).await;
+            manager
+                .close_secret_chat(secret_chat_id)
+                .await
+                .unwrap_or_else(|error| cli::exit_with_error(error));
+        }
+        Some("key") => key(manager, args).await,
+        Some("ttl") => ttl(manager, args).await,
+        Some(other) => cli::exit_with_usage(&format!("unknown secret command '{}'", other)),
+    }
+}
+
+/// The secret chat behind the chat given as the first argument.
+async fn secret_chat_id(manager: &Manager, args: &Args) -> i32 {
+    let chat = manager
+        .get_chat(args.chat(manager, 1).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    if let ChatType::ChatTypeSecret { secret_chat_id, .. } = chat.chat_type {
+        secret_chat_id
+    } else {
+        cli::exit_with_usage(&format!("'{}' is not a secret chat", chat.title))
+    }
+}
+
+fn state(state: &SecretChatState) -> &'static str {
+    match state {
+        SecretChatState::SecretChatStatePending => "pending",
+        SecretChatState::SecretChatStateReady => "ready",
+        SecretChatState::SecretChatStateClosed => "closed",
+    }
+}
+
+async fn list(manager: &Manager) {
+    let chat_ids = manager.get_chats(200).await.unwrap_or_else(|error| cli::exit_with_error(error)).chat_ids;
+    for id in chat_ids {
+        let chat = manager.get_chat(id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+        let secret_chat_id = match chat.chat_type {
+            ChatType::ChatTypeSecret { secret_chat_id, .. } => secret_chat_id,
+            _ => continue,
+        };
+        let secret_chat = manager
+            .get_secret_chat(secret_chat_id)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+
+        let timer = if chat.message_ttl > 0 {
+            format!(" ⏱ {}", view::ttl(chat.message_ttl))
+        } else {
+            "".to_owned()
+        };
+
+        println!(
+            "{} {} {}{}",
+            chat.title.bold(),
+            format!("#{}", chat.id).bright_black(),
+            state(&secret_chat.state),
+            timer.red()
+        );
+    }
+}
+
+async fn new(manager: &Manager, args: &Args) {
+    let user = args
+        .positional(1)
+        .unwrap_or_else(|| cli::exit_with_usage("no user given"));
+    let chat = manager
+        .create_new_secret_chat(contacts::user_id(manager, user).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    println!("{} {}", chat.title.bold(), format!("#{}", chat.id).bright_black());
+    println!("The chat is ready once {} comes online", chat.title);
+}
+
+async fn key(manager: &Manager, args: &Args) {
+    let secret_chat = manager
+        .get_secret_chat(secret_chat_id(manager, args).await)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    if secret_chat.state != SecretChatState::SecretChatStateReady {
+        eprintln!("The secret chat is {}, there's no key to compare", state(&secret_chat.state));
+        std::process::exit(1)
+    }
+
+    println!("{}", view::key_fingerprint(&secret_chat));
+    println!("\nIf this looks the same on the other side's device, the chat is end-to-end encrypted");
+}
+
+/// `ttl <chat> <30s|5m|1h|1d|1w|off>`
+async fn ttl(manager: &Manager, args: &Args) {
+    let chat_id = args.chat(manager, 1).await;
+    let value = args
+        .positional(2)
+        .unwrap_or_else(|| cli::exit_with_usage("no timer given"));
+
+    let seconds = if value == "off" {
+        0
+    } else {
+        cli::duration(value).unwrap_or_else(|| cli::exit_with_usage("expected a timer like 30s, 5m, 1h, 1d, 1w or off"))
+    };
+
+    manager
+        .set_chat_message_ttl(chat_id, seconds)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+}
@@ -2,230 +2,1538 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Error {
-    code: i32,
-    message: String,
+    pub code: i32,
+    pub message: String,
+}
+
+/// TDLib sends 64-bit integers as strings, since JSON numbers can't hold all of them precisely.
+mod int64 {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(value) => value.parse().map_err(de::Error::custom),
+            serde_json::Value::Number(value) => value.as_i64().ok_or_else(|| de::Error::custom("integer out of range")),
+            other => Err(de::Error::custom(format!("expected an integer, got {}", other))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OptionValueBoolean {
+    pub value: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OptionValueInteger {
+    #[serde(with = "int64")] pub value: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OptionValueString {
+    pub value: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum OptionValue {
+    OptionValueBoolean(OptionValueBoolean),
+    OptionValueEmpty,
+    OptionValueInteger(OptionValueInteger),
+    OptionValueString(OptionValueString)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateOption {
+    pub name: String,
+    pub value: OptionValue
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetOption {
+    pub name: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetOption {
+    pub name: String,
+    pub value: OptionValue
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum AuthorizationStateType {
+    AuthorizationStateWaitTdlibParameters,
+    AuthorizationStateWaitEncryptionKey { is_encrypted: bool },
+    AuthorizationStateWaitPhoneNumber,
+    AuthorizationStateWaitCode,
+    AuthorizationStateReady,
+    AuthorizationStateClosing
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UpdateAuthorizationState {
+    pub authorization_state: AuthorizationStateType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetAuthenticationPhoneNumber {
+    pub phone_number: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckAuthenticationCode {
+    pub code: String,
+}
+
+/// Encryption keys are bytes, which TDLib takes base64 encoded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckDatabaseEncryptionKey {
+    pub encryption_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetDatabaseEncryptionKey {
+    pub new_encryption_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct User {
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+    pub phone_number: String,
+    pub id: i64,
+    pub status: UserStatus,
+    pub is_contact: bool,
+    pub is_mutual_contact: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateUser {
+    pub user: User,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum UserStatus {
+    UserStatusEmpty,
+    UserStatusOnline { expires: i32 },
+    UserStatusOffline { was_online: i32 },
+    UserStatusRecently,
+    UserStatusLastWeek,
+    UserStatusLastMonth
+}
+
+impl UserStatus {
+    /// The status as of `now` (unixtime). TDLib doesn't send an update when an online status
+    /// expires, so an expired one is turned into offline since the expiry time.
+    pub fn at(&self, now: i32) -> UserStatus {
+        match self {
+            UserStatus::UserStatusOnline { expires } if *expires <= now => {
+                UserStatus::UserStatusOffline { was_online: *expires }
+            }
+            it => it.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateUserStatus {
+    pub user_id: i64,
+    pub status: UserStatus
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetChats {
+    pub limit: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoadChats {
+    pub limit: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetChat {
+    pub chat_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchChats {
+    pub query: String,
+    pub limit: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetUser {
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Chats {
+    pub total_count: i32,
+    pub chat_ids: Vec<i64>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FormattedText {
+    pub text: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageText {
+    pub text: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalFile {
+    pub path: String,
+    pub is_downloading_active: bool,
+    pub is_downloading_completed: bool,
+    pub downloaded_size: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemoteFile {
+    pub id: String,
+    pub is_uploading_active: bool,
+    pub is_uploading_completed: bool,
+    pub uploaded_size: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct File {
+    pub id: i32,
+    pub size: i64,
+    pub expected_size: i64,
+    pub local: LocalFile,
+    pub remote: RemoteFile
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DownloadFile {
+    pub file_id: i32,
+    /// 1 to 32, higher is downloaded first
+    pub priority: i32,
+    pub offset: i64,
+    pub limit: i64,
+    pub synchronous: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateFile {
+    pub file: File
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Document {
+    pub file_name: String,
+    pub mime_type: String,
+    pub document: File
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotoSize {
+    #[serde(rename = "type")] pub size_type: String,
+    pub photo: File,
+    pub width: i32,
+    pub height: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Photo {
+    pub sizes: Vec<PhotoSize>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Video {
+    pub duration: i32,
+    pub width: i32,
+    pub height: i32,
+    pub file_name: String,
+    pub mime_type: String,
+    pub video: File
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Audio {
+    pub duration: i32,
+    pub title: String,
+    pub performer: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub audio: File
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoiceNote {
+    pub duration: i32,
+    pub mime_type: String,
+    pub voice: File
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageDocument {
+    pub document: Document,
+    pub caption: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessagePhoto {
+    pub photo: Photo,
+    pub caption: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageVideo {
+    pub video: Video,
+    pub caption: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageAudio {
+    pub audio: Audio,
+    pub caption: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageVoiceNote {
+    pub voice_note: VoiceNote,
+    pub caption: FormattedText
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum MessageContent {
+    MessageAnimatedEmoji, 
+    MessageAnimation, 
+    MessageAudio(MessageAudio),
+    MessageBasicGroupChatCreate, 
+    MessageCall, 
+    MessageChatAddMembers, 
+    MessageChatChangePhoto, 
+    MessageChatChangeTitle, 
+    MessageChatDeleteMember, 
+    MessageChatDeletePhoto, 
+    MessageChatJoinByLink, 
+    MessageChatJoinByRequest, 
+    MessageChatSetTheme, 
+    MessageChatSetTtl, 
+    MessageChatUpgradeFrom, 
+    MessageChatUpgradeTo, 
+    MessageContact, 
+    MessageContactRegistered, 
+    MessageCustomServiceAction, 
+    MessageDice, 
+    MessageDocument(MessageDocument),
+    MessageExpiredPhoto, 
+    MessageExpiredVideo, 
+    MessageGame, 
+    MessageGameScore, 
+    MessageInviteVideoChatParticipants, 
+    MessageInvoice, 
+    MessageLocation, 
+    MessagePassportDataReceived, 
+    MessagePassportDataSent, 
+    MessagePaymentSuccessful, 
+    MessagePaymentSuccessfulBot, 
+    MessagePhoto(MessagePhoto),
+    MessagePinMessage, 
+    MessagePoll(MessagePoll),
+    MessageProximityAlertTriggered, 
+    MessageScreenshotTaken, 
+    MessageSticker, 
+    MessageSupergroupChatCreate, 
+    MessageText(MessageText),
+    MessageUnsupported, 
+    MessageVenue, 
+    MessageVideo(MessageVideo),
+    MessageVideoChatEnded, 
+    MessageVideoChatScheduled, 
+    MessageVideoChatStarted, 
+    MessageVideoNote, 
+    MessageVoiceNote(MessageVoiceNote),
+    MessageWebsiteConnected
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollOption {
+    pub text: String,
+    pub voter_count: i32,
+    pub vote_percentage: i32,
+    pub is_chosen: bool,
+    pub is_being_chosen: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum PollType {
+    PollTypeRegular { allow_multiple_answers: bool },
+    /// `correct_option_id` is -1 until the user answered the quiz or it was closed
+    PollTypeQuiz { correct_option_id: i32, explanation: Option<FormattedText> }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Poll {
+    pub id: String,
+    pub question: String,
+    pub options: Vec<PollOption>,
+    pub total_voter_count: i32,
+    pub is_anonymous: bool,
+    #[serde(rename = "type")] pub poll_type: PollType,
+    pub open_period: i32,
+    pub close_date: i32,
+    pub is_closed: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessagePoll {
+    pub poll: Poll
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdatePoll {
+    pub poll: Poll
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdatePollAnswer {
+    pub poll_id: String,
+    pub user_id: i64,
+    pub option_ids: Vec<i32>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetPollAnswer {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub option_ids: Vec<i32>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StopPoll {
+    pub chat_id: i64,
+    pub message_id: i64
+}
+
+impl MessageContent {
+    /// The file attached to the message, if any. For photos this is the largest size.
+    pub fn file(&self) -> Option<&File> {
+        match self {
+            MessageContent::MessageDocument(it) => Some(&it.document.document),
+            MessageContent::MessagePhoto(it) => it.photo.sizes.last().map(|size| &size.photo),
+            MessageContent::MessageVideo(it) => Some(&it.video.video),
+            MessageContent::MessageAudio(it) => Some(&it.audio.audio),
+            MessageContent::MessageVoiceNote(it) => Some(&it.voice_note.voice),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum InputFile {
+    InputFileId { id: i32 },
+    InputFileLocal { path: String }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputThumbnail {
+    pub thumbnail: InputFile,
+    pub width: i32,
+    pub height: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessageText {
+    pub text: FormattedText,
+    pub disable_web_page_preview: bool,
+    pub clear_draft: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessageDocument {
+    pub document: InputFile,
+    pub thumbnail: Option<InputThumbnail>,
+    pub disable_content_type_detection: bool,
+    pub caption: Option<FormattedText>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessagePhoto {
+    pub photo: InputFile,
+    pub thumbnail: Option<InputThumbnail>,
+    pub width: i32,
+    pub height: i32,
+    pub caption: Option<FormattedText>,
+    pub ttl: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessageVideo {
+    pub video: InputFile,
+    pub thumbnail: Option<InputThumbnail>,
+    pub duration: i32,
+    pub width: i32,
+    pub height: i32,
+    pub supports_streaming: bool,
+    pub caption: Option<FormattedText>,
+    pub ttl: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessageAudio {
+    pub audio: InputFile,
+    pub album_cover_thumbnail: Option<InputThumbnail>,
+    pub duration: i32,
+    pub title: String,
+    pub performer: String,
+    pub caption: Option<FormattedText>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessageVoiceNote {
+    pub voice_note: InputFile,
+    pub duration: i32,
+    pub waveform: String,
+    pub caption: Option<FormattedText>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputMessagePoll {
+    pub question: String,
+    pub options: Vec<String>,
+    pub is_anonymous: bool,
+    #[serde(rename = "type")] pub poll_type: PollType,
+    pub open_period: i32,
+    pub close_date: i32,
+    pub is_closed: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum InputMessageContent {
+    InputMessageText(InputMessageText),
+    InputMessageDocument(InputMessageDocument),
+    InputMessagePhoto(InputMessagePhoto),
+    InputMessageVideo(InputMessageVideo),
+    InputMessageAudio(InputMessageAudio),
+    InputMessageVoiceNote(InputMessageVoiceNote),
+    InputMessagePoll(InputMessagePoll)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendMessage {
+    pub chat_id: i64,
+    pub message_thread_id: i64,
+    pub reply_to_message_id: i64,
+    pub input_message_content: InputMessageContent
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageSenderUser {
+    pub user_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageSenderChat {
+    pub chat_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum MessageSender {
+    MessageSenderUser(MessageSenderUser),
+    MessageSenderChat(MessageSenderChat)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Message {
+    pub id: i64,
+    pub chat_id: i64,
+    pub is_outgoing: bool,
+    pub can_be_edited: bool,
+    pub can_be_forwarded: bool,
+    pub can_be_deleted_only_for_self: bool,
+    pub can_be_deleted_for_all_users: bool,
+    pub date: i32,
+    pub edit_date: i32,
+    pub reply_in_chat_id: i64,
+    pub reply_to_message_id: i64,
+    pub message_thread_id: i64,
+    pub interaction_info: Option<MessageInteractionInfo>,
+    /// Self-destruct timer in seconds, which starts once the message is viewed. 0 if there's none
+    #[serde(default)] pub ttl: i32,
+    #[serde(default)] pub ttl_expires_in: f64,
+    #[serde(default)] pub contains_unread_mention: bool,
+    pub content: MessageContent,
+    pub sender_id: MessageSender
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageReplyInfo {
+    pub reply_count: i32,
+    pub last_read_inbox_message_id: i64,
+    pub last_message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ReactionType {
+    ReactionTypeEmoji { emoji: String },
+    ReactionTypeCustomEmoji { custom_emoji_id: String }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageReaction {
+    #[serde(rename = "type")] pub reaction_type: ReactionType,
+    pub total_count: i32,
+    pub is_chosen: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageInteractionInfo {
+    pub view_count: i32,
+    pub forward_count: i32,
+    pub reply_info: Option<MessageReplyInfo>,
+    #[serde(default)] pub reactions: Vec<MessageReaction>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ChatAvailableReactions {
+    ChatAvailableReactionsAll,
+    ChatAvailableReactionsSome { reactions: Vec<ReactionType> }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddMessageReaction {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub reaction_type: ReactionType,
+    pub is_big: bool,
+    pub update_recent_reactions: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemoveMessageReaction {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub reaction_type: ReactionType
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReadAllChatReactions {
+    pub chat_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateActiveEmojiReactions {
+    pub emojis: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatAvailableReactions {
+    pub chat_id: i64,
+    pub available_reactions: ChatAvailableReactions
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatUnreadReactionCount {
+    pub chat_id: i64,
+    pub unread_reaction_count: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetRepliedMessage {
+    pub chat_id: i64,
+    pub message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetMessageThread {
+    pub chat_id: i64,
+    pub message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetMessageThreadHistory {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub from_message_id: i64,
+    pub offset: i32,
+    pub limit: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageThreadInfo {
+    pub chat_id: i64,
+    pub message_thread_id: i64,
+    pub reply_info: Option<MessageReplyInfo>,
+    pub unread_message_count: i32,
+    pub messages: Vec<Message>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMessageInteractionInfo {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub interaction_info: Option<MessageInteractionInfo>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Messages {
+    pub total_count: i32,
+    pub messages: Vec<Option<Message>>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetMessage {
+    pub chat_id: i64,
+    pub message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetChatHistory {
+    pub chat_id: i64,
+    pub from_message_id: i64,
+    pub offset: i32,
+    pub limit: i32,
+    pub only_local: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum SearchMessagesFilter {
+    SearchMessagesFilterEmpty,
+    SearchMessagesFilterAnimation,
+    SearchMessagesFilterAudio,
+    SearchMessagesFilterDocument,
+    SearchMessagesFilterPhoto,
+    SearchMessagesFilterVideo,
+    SearchMessagesFilterVoiceNote,
+    SearchMessagesFilterPhotoAndVideo,
+    SearchMessagesFilterUrl,
+    SearchMessagesFilterMention,
+    SearchMessagesFilterPinned
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchChatMessages {
+    pub chat_id: i64,
+    pub query: String,
+    pub sender_id: Option<MessageSender>,
+    pub from_message_id: i64,
+    pub offset: i32,
+    pub limit: i32,
+    pub filter: SearchMessagesFilter,
+    pub message_thread_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchMessages {
+    pub query: String,
+    pub offset_date: i32,
+    pub offset_chat_id: i64,
+    pub offset_message_id: i64,
+    pub limit: i32,
+    pub filter: SearchMessagesFilter,
+    pub min_date: i32,
+    pub max_date: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EditMessageText {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub input_message_content: InputMessageContent
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteMessages {
+    pub chat_id: i64,
+    pub message_ids: Vec<i64>,
+    pub revoke: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForwardMessages {
+    pub chat_id: i64,
+    pub from_chat_id: i64,
+    pub message_ids: Vec<i64>,
+    pub send_copy: bool,
+    pub remove_caption: bool,
+    pub only_preview: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateNewMessage {
+    pub message: Message
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMessageContent {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub new_content: MessageContent
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMessageEdited {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub edit_date: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateDeleteMessages {
+    pub chat_id: i64,
+    pub message_ids: Vec<i64>,
+    pub is_permanent: bool,
+    pub from_cache: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMessageSendSucceeded {
+    pub message: Message,
+    pub old_message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMessageSendFailed {
+    pub message: Message,
+    pub old_message_id: i64,
+    pub error_code: i32,
+    pub error_message: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ChatType {
+    ChatTypeBasicGroup { basic_group_id: i64 },
+    ChatTypePrivate { user_id: i64 },
+    ChatTypeSecret { secret_chat_id: i32, user_id: i64 },
+    ChatTypeSupergroup { supergroup_id: i64, is_channel: bool }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum SecretChatState {
+    SecretChatStatePending,
+    SecretChatStateReady,
+    SecretChatStateClosed
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecretChat {
+    pub id: i32,
+    pub user_id: i64,
+    pub state: SecretChatState,
+    pub is_outbound: bool,
+    /// Base64 of the encryption key hash, to be compared between both devices
+    pub key_hash: String,
+    pub layer: i32
+}
+
+impl SecretChat {
+    /// The key hash as bytes: 16 of them for layers before 46, 36 after.
+    pub fn key_hash_bytes(&self) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        let mut bytes = Vec::new();
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        for char in self.key_hash.bytes().take_while(|it| *it != b'=') {
+            let value = match ALPHABET.iter().position(|it| *it == char) {
+                Some(value) => value as u32,
+                None => continue,
+            };
+            buffer = (buffer << 6) | value;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+
+        bytes
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetSecretChat {
+    pub secret_chat_id: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateNewSecretChat {
+    pub user_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CloseSecretChat {
+    pub secret_chat_id: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetChatMessageTtl {
+    pub chat_id: i64,
+    pub ttl: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ConnectionState {
+    ConnectionStateWaitingForNetwork,
+    ConnectionStateConnectingToProxy,
+    ConnectionStateConnecting,
+    /// Connected, fetching what changed while offline
+    ConnectionStateUpdating,
+    ConnectionStateReady
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateConnectionState {
+    pub state: ConnectionState
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateSecretChat {
+    pub secret_chat: SecretChat
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatMessageTtl {
+    pub chat_id: i64,
+    pub message_ttl: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatPermissions {
+    pub can_send_messages: bool,
+    pub can_send_media_messages: bool,
+    pub can_send_polls: bool,
+    pub can_send_other_messages: bool,
+    pub can_add_web_page_previews: bool,
+    pub can_change_info: bool,
+    pub can_invite_users: bool,
+    pub can_pin_messages: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatAdministratorRights {
+    pub can_manage_chat: bool,
+    pub can_change_info: bool,
+    pub can_post_messages: bool,
+    pub can_edit_messages: bool,
+    pub can_delete_messages: bool,
+    pub can_invite_users: bool,
+    pub can_restrict_members: bool,
+    pub can_pin_messages: bool,
+    pub can_manage_video_chats: bool,
+    pub can_promote_members: bool,
+    pub is_anonymous: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ChatMemberStatus {
+    ChatMemberStatusCreator { custom_title: String, is_anonymous: bool, is_member: bool },
+    ChatMemberStatusAdministrator { custom_title: String, can_be_edited: bool, rights: ChatAdministratorRights },
+    ChatMemberStatusMember,
+    ChatMemberStatusRestricted { is_member: bool, restricted_until_date: i32, permissions: ChatPermissions },
+    ChatMemberStatusLeft,
+    ChatMemberStatusBanned { banned_until_date: i32 }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatMember {
+    pub member_id: MessageSender,
+    pub inviter_user_id: i64,
+    pub joined_chat_date: i32,
+    pub status: ChatMemberStatus
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatMembers {
+    pub total_count: i32,
+    pub members: Vec<ChatMember>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum ChatMembersFilter {
+    ChatMembersFilterContacts,
+    ChatMembersFilterAdministrators,
+    ChatMembersFilterMembers,
+    ChatMembersFilterRestricted,
+    ChatMembersFilterBanned,
+    ChatMembersFilterBots
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum SupergroupMembersFilter {
+    SupergroupMembersFilterRecent,
+    SupergroupMembersFilterContacts { query: String },
+    SupergroupMembersFilterAdministrators,
+    SupergroupMembersFilterSearch { query: String },
+    SupergroupMembersFilterRestricted { query: String },
+    SupergroupMembersFilterBanned { query: String },
+    SupergroupMembersFilterBots
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BasicGroup {
+    pub id: i64,
+    pub member_count: i32,
+    pub status: ChatMemberStatus,
+    pub is_active: bool,
+    pub upgraded_to_supergroup_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Supergroup {
+    pub id: i64,
+    pub username: String,
+    pub date: i32,
+    pub status: ChatMemberStatus,
+    pub member_count: i32,
+    pub is_channel: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatInviteLink {
+    pub invite_link: String,
+    pub name: String,
+    pub creator_user_id: i64,
+    pub date: i32,
+    pub expiration_date: i32,
+    pub member_limit: i32,
+    pub member_count: i32,
+    pub pending_join_request_count: i32,
+    pub creates_join_request: bool,
+    pub is_primary: bool,
+    pub is_revoked: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatInviteLinks {
+    pub total_count: i32,
+    pub invite_links: Vec<ChatInviteLink>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatInviteLinkInfo {
+    /// 0 unless the user is already a member or the chat is public
+    pub chat_id: i64,
+    pub title: String,
+    pub member_count: i32,
+    pub creates_join_request: bool,
+    pub is_public: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckChatInviteLink {
+    pub invite_link: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BasicGroupFullInfo {
+    pub description: String,
+    pub creator_user_id: i64,
+    pub members: Vec<ChatMember>,
+    pub invite_link: Option<ChatInviteLink>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SupergroupFullInfo {
+    pub description: String,
+    pub member_count: i32,
+    pub administrator_count: i32,
+    pub restricted_count: i32,
+    pub banned_count: i32,
+    pub linked_chat_id: i64,
+    pub invite_link: Option<ChatInviteLink>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatJoinRequest {
+    pub user_id: i64,
+    pub date: i32,
+    pub bio: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatJoinRequests {
+    pub total_count: i32,
+    pub requests: Vec<ChatJoinRequest>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum InputChatPhoto {
+    InputChatPhotoStatic { photo: InputFile }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetBasicGroup {
+    pub basic_group_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetBasicGroupFullInfo {
+    pub basic_group_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetSupergroup {
+    pub supergroup_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetSupergroupFullInfo {
+    pub supergroup_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchChatMembers {
+    pub chat_id: i64,
+    pub query: String,
+    pub limit: i32,
+    pub filter: Option<ChatMembersFilter>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetSupergroupMembers {
+    pub supergroup_id: i64,
+    pub filter: SupergroupMembersFilter,
+    pub offset: i32,
+    pub limit: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddChatMember {
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub forward_limit: i32
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OptionValueBoolean {
-    value: bool
+pub struct SetChatMemberStatus {
+    pub chat_id: i64,
+    pub member_id: MessageSender,
+    pub status: ChatMemberStatus
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OptionValueInteger {
-    value: String
+pub struct BanChatMember {
+    pub chat_id: i64,
+    pub member_id: MessageSender,
+    pub banned_until_date: i32,
+    pub revoke_messages: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OptionValueString {
-    value: String
+pub struct SetChatTitle {
+    pub chat_id: i64,
+    pub title: String
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "@type", rename_all = "camelCase")]
-pub enum OptionValue {
-    OptionValueBoolean(OptionValueBoolean),
-    OptionValueEmpty,
-    OptionValueInteger(OptionValueInteger),
-    OptionValueString(OptionValueString)
+pub struct SetChatDescription {
+    pub chat_id: i64,
+    pub description: String
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UpdateOption {
-    name: String,
-    value: OptionValue
+pub struct SetChatPhoto {
+    pub chat_id: i64,
+    pub photo: Option<InputChatPhoto>
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-#[serde(tag = "@type", rename_all = "camelCase")]
-pub enum AuthorizationStateType {
-    AuthorizationStateWaitTdlibParameters,
-    AuthorizationStateWaitPhoneNumber,
-    AuthorizationStateWaitCode,
-    AuthorizationStateReady,
-    AuthorizationStateClosing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetChatInviteLinks {
+    pub chat_id: i64,
+    pub creator_user_id: i64,
+    pub is_revoked: bool,
+    pub offset_date: i32,
+    pub offset_invite_link: String,
+    pub limit: i32
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-pub struct UpdateAuthorizationState {
-    pub authorization_state: AuthorizationStateType,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateChatInviteLink {
+    pub chat_id: i64,
+    pub name: String,
+    pub expiration_date: i32,
+    pub member_limit: i32,
+    pub creates_join_request: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SetAuthenticationPhoneNumber {
-    pub phone_number: String,
+pub struct RevokeChatInviteLink {
+    pub chat_id: i64,
+    pub invite_link: String
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct CheckAuthenticationCode {
-    pub code: String,
+pub struct GetChatJoinRequests {
+    pub chat_id: i64,
+    pub invite_link: String,
+    pub query: String,
+    pub offset_request: Option<ChatJoinRequest>,
+    pub limit: i32
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct User {
+pub struct ProcessChatJoinRequest {
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub approve: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateBasicGroup {
+    pub basic_group: BasicGroup
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateBasicGroupFullInfo {
+    pub basic_group_id: i64,
+    pub basic_group_full_info: BasicGroupFullInfo
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateSupergroup {
+    pub supergroup: Supergroup
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateSupergroupFullInfo {
+    pub supergroup_id: i64,
+    pub supergroup_full_info: SupergroupFullInfo
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateNewChatJoinRequest {
+    pub chat_id: i64,
+    pub request: ChatJoinRequest,
+    pub invite_link: Option<ChatInviteLink>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Contact {
+    pub phone_number: String,
     pub first_name: String,
     pub last_name: String,
-    pub username: String,
-    pub id: i64,
-    pub status: UserStatus
+    pub vcard: String,
+    pub user_id: i64
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UpdateUser {
-    pub user: User,
+pub struct Users {
+    pub total_count: i32,
+    pub user_ids: Vec<i64>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "@type", rename_all = "camelCase")]
-pub enum UserStatus {
-    UserStatusEmpty,
-    UserStatusOnline,
-    UserStatusOffline,
-    UserStatusRecently,
-    UserStatusLastWeek,
-    UserStatusLastMonth
+pub struct ImportedContacts {
+    /// 0 for contacts that aren't registered in Telegram
+    pub user_ids: Vec<i64>,
+    pub importer_count: Vec<i32>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UpdateUserStatus {
-    pub user_id: i64,
-    pub status: UserStatus
+pub struct SearchContacts {
+    pub query: String,
+    pub limit: i32
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetChats {
-    pub limit: i32,
+pub struct ImportContacts {
+    pub contacts: Vec<Contact>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct LoadChats {
-    pub limit: i32,
+pub struct AddContact {
+    pub contact: Contact,
+    pub share_phone_number: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetChat {
-    pub chat_id: i64,
+pub struct RemoveContacts {
+    pub user_ids: Vec<i64>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GetUser {
+pub struct SearchPublicChat {
+    pub username: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatePrivateChat {
     pub user_id: i64,
+    pub force: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Chats {
-    pub total_count: i32,
-    pub chat_ids: Vec<i64>
+pub struct Chat {
+    pub id: i64,
+    pub title: String,
+    /// `None` in chats without messages, like a secret chat that was just created
+    pub last_message: Option<Message>,
+    pub is_marked_as_unread: bool,
+    pub unread_count: i32,
+    pub unread_mention_count: i32,
+    pub unread_reaction_count: i32,
+    pub available_reactions: ChatAvailableReactions,
+    pub last_read_inbox_message_id: i64,
+    pub last_read_outbox_message_id: i64,
+    /// Self-destruct timer of new messages in seconds, 0 if disabled
+    #[serde(default)] pub message_ttl: i32,
+    #[serde(default)] pub notification_settings: ChatNotificationSettings,
+    #[serde(rename = "type")] pub chat_type: ChatType
+}
+
+/// Notification settings of a chat. Each `use_default_*` means the value of the chat's scope applies instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatNotificationSettings {
+    pub use_default_mute_for: bool,
+    /// Seconds until the chat is unmuted, 0 if it isn't muted
+    pub mute_for: i32,
+    pub use_default_show_preview: bool,
+    pub show_preview: bool,
+    pub use_default_disable_mention_notifications: bool,
+    pub disable_mention_notifications: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ScopeNotificationSettings {
+    pub mute_for: i32,
+    pub show_preview: bool,
+    pub disable_mention_notifications: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum NotificationSettingsScope {
+    NotificationSettingsScopePrivateChats,
+    NotificationSettingsScopeGroupChats,
+    NotificationSettingsScopeChannelChats
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct FormattedText {
-    pub text: String
+pub struct GetScopeNotificationSettings {
+    pub scope: NotificationSettingsScope
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MessageText {
-    pub text: FormattedText
+pub struct UpdateChatNotificationSettings {
+    pub chat_id: i64,
+    pub notification_settings: ChatNotificationSettings
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "@type", rename_all = "camelCase")]
-pub enum MessageContent {
-    MessageAnimatedEmoji, 
-    MessageAnimation, 
-    MessageAudio, 
-    MessageBasicGroupChatCreate, 
-    MessageCall, 
-    MessageChatAddMembers, 
-    MessageChatChangePhoto, 
-    MessageChatChangeTitle, 
-    MessageChatDeleteMember, 
-    MessageChatDeletePhoto, 
-    MessageChatJoinByLink, 
-    MessageChatJoinByRequest, 
-    MessageChatSetTheme, 
-    MessageChatSetTtl, 
-    MessageChatUpgradeFrom, 
-    MessageChatUpgradeTo, 
-    MessageContact, 
-    MessageContactRegistered, 
-    MessageCustomServiceAction, 
-    MessageDice, 
-    MessageDocument, 
-    MessageExpiredPhoto, 
-    MessageExpiredVideo, 
-    MessageGame, 
-    MessageGameScore, 
-    MessageInviteVideoChatParticipants, 
-    MessageInvoice, 
-    MessageLocation, 
-    MessagePassportDataReceived, 
-    MessagePassportDataSent, 
-    MessagePaymentSuccessful, 
-    MessagePaymentSuccessfulBot, 
-    MessagePhoto, 
-    MessagePinMessage, 
-    MessagePoll, 
-    MessageProximityAlertTriggered, 
-    MessageScreenshotTaken, 
-    MessageSticker, 
-    MessageSupergroupChatCreate, 
-    MessageText(MessageText),
-    MessageUnsupported, 
-    MessageVenue, 
-    MessageVideo, 
-    MessageVideoChatEnded, 
-    MessageVideoChatScheduled, 
-    MessageVideoChatStarted, 
-    MessageVideoNote, 
-    MessageVoiceNote, 
-    MessageWebsiteConnected
+pub struct UpdateScopeNotificationSettings {
+    pub scope: NotificationSettingsScope,
+    pub notification_settings: ScopeNotificationSettings
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MessageSenderUser {
-    pub user_id: i64
+pub struct OpenChat {
+    pub chat_id: i64
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MessageSenderChat {
+pub struct CloseChat {
+    pub chat_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ViewMessages {
+    pub chat_id: i64,
+    pub message_thread_id: i64,
+    pub message_ids: Vec<i64>,
+    pub force_read: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToggleChatIsMarkedAsUnread {
+    pub chat_id: i64,
+    pub is_marked_as_unread: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReadAllChatMentions {
     pub chat_id: i64
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateNewChat {
+    pub chat: Chat
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatTitle {
+    pub chat_id: i64,
+    pub title: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatLastMessage {
+    pub chat_id: i64,
+    pub last_message: Option<Message>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatReadInbox {
+    pub chat_id: i64,
+    pub last_read_inbox_message_id: i64,
+    pub unread_count: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatReadOutbox {
+    pub chat_id: i64,
+    pub last_read_outbox_message_id: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatIsMarkedAsUnread {
+    pub chat_id: i64,
+    pub is_marked_as_unread: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatUnreadMentionCount {
+    pub chat_id: i64,
+    pub unread_mention_count: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "@type", rename_all = "camelCase")]
-pub enum MessageSender {
-    MessageSenderUser(MessageSenderUser),
-    MessageSenderChat(MessageSenderChat)
+pub enum ChatAction {
+    ChatActionTyping,
+    ChatActionRecordingVideo,
+    ChatActionUploadingVideo { progress: i32 },
+    ChatActionRecordingVoiceNote,
+    ChatActionUploadingVoiceNote { progress: i32 },
+    ChatActionUploadingPhoto { progress: i32 },
+    ChatActionUploadingDocument { progress: i32 },
+    ChatActionChoosingSticker,
+    ChatActionChoosingLocation,
+    ChatActionChoosingContact,
+    ChatActionStartPlayingGame,
+    ChatActionRecordingVideoNote,
+    ChatActionUploadingVideoNote { progress: i32 },
+    ChatActionWatchingAnimations { emoji: String },
+    ChatActionCancel
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Message {
-    pub id: i64,
+pub struct SendChatAction {
     pub chat_id: i64,
-    pub is_outgoing: bool,
-    pub date: i32,
-    pub content: MessageContent,
-    pub sender_id: MessageSender
+    pub message_thread_id: i64,
+    pub action: ChatAction
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateChatAction {
+    pub chat_id: i64,
+    pub message_thread_id: i64,
+    pub sender_id: MessageSender,
+    pub action: ChatAction
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "@type", rename_all = "camelCase")]
-pub enum ChatType {
-    ChatTypeBasicGroup,
-    ChatTypePrivate,
-    ChatTypeSecret,
-    ChatTypeSupergroup
+pub enum ProxyType {
+    ProxyTypeSocks5 { username: String, password: String },
+    /// With `http_only` the proxy is used for HTTP requests only, without CONNECT
+    ProxyTypeHttp { username: String, password: String, http_only: bool },
+    ProxyTypeMtproto { secret: String }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Chat {
-    pub id: i64,
-    pub title: String,
-    pub last_message: Message,
-    pub is_marked_as_unread: bool,
-    pub unread_count: i32,
-    #[serde(rename = "type")] pub chat_type: ChatType
+pub struct Proxy {
+    pub id: i32,
+    pub server: String,
+    pub port: i32,
+    pub last_used_date: i32,
+    pub is_enabled: bool,
+    #[serde(rename = "type")] pub proxy_type: ProxyType
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Proxies {
+    pub proxies: Vec<Proxy>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddProxy {
+    pub server: String,
+    pub port: i32,
+    pub enable: bool,
+    #[serde(rename = "type")] pub proxy_type: ProxyType
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnableProxy {
+    pub proxy_id: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RemoveProxy {
+    pub proxy_id: i32
+}
+
+/// Pings Telegram through the proxy, or directly with proxy id 0.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PingProxy {
+    pub proxy_id: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Seconds {
+    pub seconds: f64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum LogStream {
+    /// stderr
+    LogStreamDefault,
+    /// Once the file is bigger than `max_file_size` bytes it's renamed to `<path>.old` and a new one is started
+    LogStreamFile { path: String, max_file_size: i64, redirect_stderr: bool },
+    LogStreamEmpty
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetLogStream {
+    pub log_stream: LogStream
+}
+
+/// 0 for fatal errors only, 1 errors, 2 warnings, 3 info, 4 debug, 5 and up everything.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetLogVerbosityLevel {
+    pub new_verbosity_level: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetLogTagVerbosityLevel {
+    pub tag: String,
+    pub new_verbosity_level: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogTags {
+    pub tags: Vec<String>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -245,6 +1553,8 @@ pub struct TdlibParameters {
     pub application_version: String,
     pub enable_storage_optimizer: bool,
     pub ignore_file_names: bool,
+    /// Base64 encoded, empty to keep the database unencrypted
+    pub database_encryption_key: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -263,6 +1573,7 @@ pub enum TgEventData {
 
     // Auth
     AuthorizationStateWaitTdlibParameters,
+    AuthorizationStateWaitEncryptionKey { is_encrypted: bool },
     AuthorizationStateWaitPhoneNumber,
     AuthorizationStateWaitCode,
     AuthorizationStateReady,
@@ -271,31 +1582,125 @@ pub enum TgEventData {
     Chats(Chats),
     Chat(Chat),
     User(User),
+    Message(Message),
+    Messages(Messages),
+    MessageThreadInfo(MessageThreadInfo),
+    BasicGroup(BasicGroup),
+    BasicGroupFullInfo(BasicGroupFullInfo),
+    Supergroup(Supergroup),
+    SupergroupFullInfo(SupergroupFullInfo),
+    ChatMembers(ChatMembers),
+    ChatInviteLink(ChatInviteLink),
+    ChatInviteLinks(ChatInviteLinks),
+    ChatInviteLinkInfo(ChatInviteLinkInfo),
+    ChatJoinRequests(ChatJoinRequests),
+    Users(Users),
+    ImportedContacts(ImportedContacts),
+    SecretChat(SecretChat),
+    ScopeNotificationSettings(ScopeNotificationSettings),
+    File(File),
+    Proxy(Proxy),
+    Proxies(Proxies),
+    Seconds(Seconds),
+    OptionValueBoolean(OptionValueBoolean),
+    OptionValueEmpty,
+    OptionValueInteger(OptionValueInteger),
+    OptionValueString(OptionValueString),
+    LogTags(LogTags),
 
     // Requests
     CheckAuthenticationCode(CheckAuthenticationCode),
+    CheckDatabaseEncryptionKey(CheckDatabaseEncryptionKey),
+    SendMessage(SendMessage),
+    EditMessageText(EditMessageText),
+    DeleteMessages(DeleteMessages),
+    ForwardMessages(ForwardMessages),
+    OpenChat(OpenChat),
+    CloseChat(CloseChat),
+    ViewMessages(ViewMessages),
+    ReadAllChatMentions(ReadAllChatMentions),
+    ReadAllChatReactions(ReadAllChatReactions),
+    AddMessageReaction(AddMessageReaction),
+    RemoveMessageReaction(RemoveMessageReaction),
+    StopPoll(StopPoll),
+    AddChatMember(AddChatMember),
+    BanChatMember(BanChatMember),
+    CreateChatInviteLink(CreateChatInviteLink),
+    RevokeChatInviteLink(RevokeChatInviteLink),
+    ProcessChatJoinRequest(ProcessChatJoinRequest),
+    ImportContacts(ImportContacts),
+    AddContact(AddContact),
+    RemoveContacts(RemoveContacts),
+    CreatePrivateChat(CreatePrivateChat),
+    CreateNewSecretChat(CreateNewSecretChat),
+    CloseSecretChat(CloseSecretChat),
+    SendChatAction(SendChatAction),
+    DownloadFile(DownloadFile),
+    AddProxy(AddProxy),
+    EnableProxy(EnableProxy),
+    DisableProxy,
+    RemoveProxy(RemoveProxy),
+    PingProxy(PingProxy),
 
     // Setters
     SetAuthenticationPhoneNumber(SetAuthenticationPhoneNumber),
     SetTdlibParameters(TdlibParameters),
+    SetDatabaseEncryptionKey(SetDatabaseEncryptionKey),
+    SetOption(SetOption),
+    SetLogStream(SetLogStream),
+    SetLogVerbosityLevel(SetLogVerbosityLevel),
+    SetLogTagVerbosityLevel(SetLogTagVerbosityLevel),
+    ToggleChatIsMarkedAsUnread(ToggleChatIsMarkedAsUnread),
+    SetPollAnswer(SetPollAnswer),
+    SetChatMemberStatus(SetChatMemberStatus),
+    SetChatTitle(SetChatTitle),
+    SetChatDescription(SetChatDescription),
+    SetChatPhoto(SetChatPhoto),
+    SetChatMessageTtl(SetChatMessageTtl),
 
     // Getters
     GetAllChats,
     GetAuthorizationState,
     GetChats(GetChats),
     GetChat(GetChat),
+    SearchChats(SearchChats),
+    CheckChatInviteLink(CheckChatInviteLink),
     GetUser(GetUser),
+    GetMessage(GetMessage),
+    GetChatHistory(GetChatHistory),
+    GetRepliedMessage(GetRepliedMessage),
+    GetMessageThread(GetMessageThread),
+    GetMessageThreadHistory(GetMessageThreadHistory),
+    GetMe,
+    GetBasicGroup(GetBasicGroup),
+    GetBasicGroupFullInfo(GetBasicGroupFullInfo),
+    GetSupergroup(GetSupergroup),
+    GetSupergroupFullInfo(GetSupergroupFullInfo),
+    SearchChatMembers(SearchChatMembers),
+    GetSupergroupMembers(GetSupergroupMembers),
+    GetChatInviteLinks(GetChatInviteLinks),
+    GetChatJoinRequests(GetChatJoinRequests),
+    GetContacts,
+    SearchContacts(SearchContacts),
+    SearchPublicChat(SearchPublicChat),
+    GetSecretChat(GetSecretChat),
+    GetScopeNotificationSettings(GetScopeNotificationSettings),
+    SearchChatMessages(SearchChatMessages),
+    SearchMessages(SearchMessages),
     LoadChats(LoadChats),
+    GetProxies,
+    GetOption(GetOption),
+    GetLogTags,
 
     // Updates
     UpdateActiveNotifications, 
     UpdateAnimatedEmojiMessageClicked, 
     UpdateAnimationSearchParameters, 
     UpdateAuthorizationState(UpdateAuthorizationState), 
-    UpdateBasicGroup, 
-    UpdateBasicGroupFullInfo, 
+    UpdateBasicGroup(UpdateBasicGroup),
+    UpdateBasicGroupFullInfo(UpdateBasicGroupFullInfo),
     UpdateCall, 
-    UpdateChatAction, 
+    UpdateChatAction(UpdateChatAction),
     UpdateChatActionBar, 
     UpdateChatDefaultDisableNotification, 
     UpdateChatDraftMessage, 
@@ -303,30 +1708,30 @@ pub enum TgEventData {
     UpdateChatHasProtectedContent, 
     UpdateChatHasScheduledMessages, 
     UpdateChatIsBlocked, 
-    UpdateChatIsMarkedAsUnread, 
-    UpdateChatLastMessage, 
+    UpdateChatIsMarkedAsUnread(UpdateChatIsMarkedAsUnread),
+    UpdateChatLastMessage(UpdateChatLastMessage),
     UpdateChatMember, 
     UpdateChatMessageSender, 
-    UpdateChatMessageTtl, 
-    UpdateChatNotificationSettings, 
+    UpdateChatMessageTtl(UpdateChatMessageTtl),
+    UpdateChatNotificationSettings(UpdateChatNotificationSettings),
     UpdateChatOnlineMemberCount, 
     UpdateChatPendingJoinRequests, 
     UpdateChatPermissions, 
     UpdateChatPhoto, 
     UpdateChatPosition, 
-    UpdateChatReadInbox, 
-    UpdateChatReadOutbox, 
+    UpdateChatReadInbox(UpdateChatReadInbox),
+    UpdateChatReadOutbox(UpdateChatReadOutbox),
     UpdateChatReplyMarkup, 
     UpdateChatTheme, 
     UpdateChatThemes, 
-    UpdateChatTitle, 
-    UpdateChatUnreadMentionCount, 
+    UpdateChatTitle(UpdateChatTitle),
+    UpdateChatUnreadMentionCount(UpdateChatUnreadMentionCount),
     UpdateChatVideoChat, 
-    UpdateConnectionState, 
-    UpdateDeleteMessages, 
+    UpdateConnectionState(UpdateConnectionState),
+    UpdateDeleteMessages(UpdateDeleteMessages),
     UpdateDiceEmojis, 
     UpdateFavoriteStickers, 
-    UpdateFile, 
+    UpdateFile(UpdateFile),
     UpdateFileGenerationStart, 
     UpdateFileGenerationStop, 
     UpdateGroupCall, 
@@ -334,43 +1739,43 @@ pub enum TgEventData {
     UpdateHavePendingNotifications, 
     UpdateInstalledStickerSets, 
     UpdateLanguagePackStrings, 
-    UpdateMessageContent, 
+    UpdateMessageContent(UpdateMessageContent),
     UpdateMessageContentOpened, 
-    UpdateMessageEdited, 
-    UpdateMessageInteractionInfo, 
+    UpdateMessageEdited(UpdateMessageEdited),
+    UpdateMessageInteractionInfo(UpdateMessageInteractionInfo),
     UpdateMessageIsPinned, 
     UpdateMessageLiveLocationViewed, 
     UpdateMessageMentionRead, 
     UpdateMessageSendAcknowledged, 
-    UpdateMessageSendFailed, 
-    UpdateMessageSendSucceeded, 
+    UpdateMessageSendFailed(UpdateMessageSendFailed),
+    UpdateMessageSendSucceeded(UpdateMessageSendSucceeded),
     UpdateNewCallbackQuery, 
     UpdateNewCallSignalingData, 
-    UpdateNewChat, 
-    UpdateNewChatJoinRequest, 
+    UpdateNewChat(UpdateNewChat),
+    UpdateNewChatJoinRequest(UpdateNewChatJoinRequest),
     UpdateNewChosenInlineResult, 
     UpdateNewCustomEvent, 
     UpdateNewCustomQuery, 
     UpdateNewInlineCallbackQuery, 
     UpdateNewInlineQuery, 
-    UpdateNewMessage, 
+    UpdateNewMessage(UpdateNewMessage),
     UpdateNewPreCheckoutQuery, 
     UpdateNewShippingQuery, 
     UpdateNotification, 
     UpdateNotificationGroup, 
     UpdateOption(UpdateOption), 
-    UpdatePoll, 
-    UpdatePollAnswer, 
+    UpdatePoll(UpdatePoll),
+    UpdatePollAnswer(UpdatePollAnswer),
     UpdateRecentStickers, 
     UpdateSavedAnimations, 
-    UpdateScopeNotificationSettings, 
-    UpdateSecretChat, 
+    UpdateScopeNotificationSettings(UpdateScopeNotificationSettings),
+    UpdateSecretChat(UpdateSecretChat),
     UpdateSelectedBackground, 
     UpdateServiceNotification, 
     UpdateStickerSet, 
     UpdateSuggestedActions, 
-    UpdateSupergroup, 
-    UpdateSupergroupFullInfo, 
+    UpdateSupergroup(UpdateSupergroup),
+    UpdateSupergroupFullInfo(UpdateSupergroupFullInfo),
     UpdateTermsOfService, 
     UpdateTrendingStickerSets, 
     UpdateUnreadChatCount, 
@@ -383,7 +1788,40 @@ pub enum TgEventData {
     UpdateDefaultReactionType,
     UpdateAttachmentMenuBots,
     UpdateFileDownloads,
-    UpdateActiveEmojiReactions,
-    UpdateChatAvailableReactions,
-    UpdateChatUnreadReactionCount
-}
\ No newline at end of file
+    UpdateActiveEmojiReactions(UpdateActiveEmojiReactions),
+    UpdateChatAvailableReactions(UpdateChatAvailableReactions),
+    UpdateChatUnreadReactionCount(UpdateChatUnreadReactionCount)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_chats_without_messages() {
+        let mut chat = json!({
+            "@type": "chat",
+            "id": 1,
+            "title": "Ada",
+            "is_marked_as_unread": false,
+            "unread_count": 0,
+            "unread_mention_count": 0,
+            "unread_reaction_count": 0,
+            "available_reactions": { "@type": "chatAvailableReactionsAll" },
+            "last_read_inbox_message_id": 0,
+            "last_read_outbox_message_id": 0,
+            "type": { "@type": "chatTypeSecret", "secret_chat_id": 2, "user_id": 3 },
+        });
+
+        match serde_json::from_value(chat.clone()) {
+            Ok(TgEventData::Chat(chat)) => assert!(chat.last_message.is_none()),
+            other => panic!("Expected a chat, got {:?}", other),
+        }
+
+        chat["last_message"] = serde_json::Value::Null;
+        match serde_json::from_value(chat) {
+            Ok(TgEventData::Chat(chat)) => assert!(chat.last_message.is_none()),
+            other => panic!("Expected a chat, got {:?}", other),
+        }
+    }
+}
@@ -15,15 +15,19 @@ pub struct Client {
 
 impl Client {
     pub fn new(timeout: f64) -> Client {
-        Client::execute(r#"{ "@type": "setLogVerbosityLevel", "new_verbosity_level": 0 }"#);
         Client {
             id: unsafe { td_create_client_id() },
             timeout: timeout,
         }
     }
 
-    pub fn execute(data: &str) {
-        unsafe { td_execute(format!("{}\0", data).as_ptr()) };
+    /// Runs a request synchronously, without a client. Only some requests can be, see `Manager::execute`.
+    pub fn execute(data: &str) -> Option<String> {
+        unsafe {
+            td_execute(format!("{}\0", data).as_ptr())
+                .as_ref()
+                .map(|it| ffi::CStr::from_ptr(it).to_string_lossy().into_owned())
+        }
     }
 
     pub fn receive(&self) -> Option<String> {
@@ -0,0 +1,43 @@
+//! TDLib objects for tests, built from JSON the way TDLib sends them.
+
+use super::api::{Chat, Message};
+use serde_json::json;
+
+/// Text message `id` in chat 1, sent by user 2 at unixtime `id`.
+pub fn message(id: i64, text: &str) -> Message {
+    serde_json::from_value(json!({
+        "id": id,
+        "chat_id": 1,
+        "is_outgoing": false,
+        "can_be_edited": false,
+        "can_be_forwarded": true,
+        "can_be_deleted_only_for_self": true,
+        "can_be_deleted_for_all_users": false,
+        "date": id,
+        "edit_date": 0,
+        "reply_in_chat_id": 0,
+        "reply_to_message_id": 0,
+        "message_thread_id": 0,
+        "contains_unread_mention": false,
+        "content": { "@type": "messageText", "text": { "text": text } },
+        "sender_id": { "@type": "messageSenderUser", "user_id": 2 },
+    }))
+    .expect("Can't build message")
+}
+
+/// Chat 1 titled `Chat`, without messages, of a type like `{ "@type": "chatTypePrivate", "user_id": 2 }`.
+pub fn chat(chat_type: serde_json::Value) -> Chat {
+    serde_json::from_value(json!({
+        "id": 1,
+        "title": "Chat",
+        "is_marked_as_unread": false,
+        "unread_count": 0,
+        "unread_mention_count": 0,
+        "unread_reaction_count": 0,
+        "available_reactions": { "@type": "chatAvailableReactionsAll" },
+        "last_read_inbox_message_id": 0,
+        "last_read_outbox_message_id": 0,
+        "type": chat_type,
+    }))
+    .expect("Can't build chat")
+}
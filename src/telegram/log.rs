@@ -0,0 +1,84 @@
+use super::{
+    api::{Error, LogStream, SetLogStream, SetLogTagVerbosityLevel, SetLogVerbosityLevel, TgEventData},
+    manager::Manager,
+};
+
+/// How much TDLib logs and where to. It's global to the process, so best set before creating a `Manager`.
+#[derive(Debug, Clone)]
+pub struct TdLog {
+    /// 0 for fatal errors only up to 5 and more for everything, see `SetLogVerbosityLevel`
+    pub verbosity: i32,
+    /// Levels for single tags like `net` or `td_requests`, overriding `verbosity`
+    pub tags: Vec<(String, i32)>,
+    /// File to log to instead of stderr
+    pub file: Option<String>,
+    /// Size in bytes after which the file is rotated
+    pub max_file_size: i64,
+}
+
+impl Default for TdLog {
+    fn default() -> Self {
+        TdLog {
+            verbosity: 0,
+            tags: Vec::new(),
+            file: None,
+            max_file_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+fn ok(answer: TgEventData) -> Result<(), Error> {
+    match answer {
+        TgEventData::Ok => Ok(()),
+        TgEventData::Error(error) => Err(error),
+        _ => panic!(),
+    }
+}
+
+/// Tags TDLib logs under, for `TdLog::tags`.
+pub fn tags() -> Vec<String> {
+    match Manager::execute(TgEventData::GetLogTags) {
+        TgEventData::LogTags(result) => result.tags,
+        _ => panic!(),
+    }
+}
+
+impl TdLog {
+    pub fn apply(&self) -> Result<(), Error> {
+        let log_stream = match &self.file {
+            Some(path) => LogStream::LogStreamFile {
+                path: path.clone(),
+                max_file_size: self.max_file_size,
+                redirect_stderr: false,
+            },
+            None => LogStream::LogStreamDefault,
+        };
+        ok(Manager::execute(TgEventData::SetLogStream(SetLogStream { log_stream })))?;
+
+        ok(Manager::execute(TgEventData::SetLogVerbosityLevel(SetLogVerbosityLevel {
+            new_verbosity_level: self.verbosity,
+        })))?;
+
+        if self.tags.is_empty() {
+            return Ok(());
+        }
+
+        // TDLib's own error doesn't say which tags there are, which makes a typo hard to fix
+        let known = tags();
+        for (tag, level) in &self.tags {
+            if !known.contains(tag) {
+                return Err(Error {
+                    code: 400,
+                    message: format!("unknown log tag '{}', there are {}", tag, known.join(", ")),
+                });
+            }
+
+            ok(Manager::execute(TgEventData::SetLogTagVerbosityLevel(SetLogTagVerbosityLevel {
+                tag: tag.clone(),
+                new_verbosity_level: *level,
+            })))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
 pub mod client;
 pub mod api;
-pub mod manager;
\ No newline at end of file
+pub mod manager;
+pub mod scheduler;
+pub mod log;
+#[cfg(test)]
+pub mod fixtures;
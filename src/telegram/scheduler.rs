@@ -0,0 +1,277 @@
+use super::api::TgEventData;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Which lane a request waits in. Interactive requests get tokens before background ones whenever both are waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Something a user is looking at, the default
+    Interactive,
+    /// Bulk jobs like exports and member listings, which can wait
+    Background,
+}
+
+/// Requests are limited per class, so a long export can't use up what sending messages needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MethodClass {
+    /// Answered by TDLib itself, never limited
+    Local,
+    Read,
+    Write,
+    Files,
+}
+
+impl MethodClass {
+    pub fn of(data: &TgEventData) -> MethodClass {
+        match data {
+            TgEventData::GetAuthorizationState
+            | TgEventData::SetTdlibParameters(_)
+            | TgEventData::CheckDatabaseEncryptionKey(_)
+            | TgEventData::SetDatabaseEncryptionKey(_)
+            | TgEventData::SetAuthenticationPhoneNumber(_)
+            | TgEventData::CheckAuthenticationCode(_)
+            | TgEventData::GetProxies
+            | TgEventData::AddProxy(_)
+            | TgEventData::EnableProxy(_)
+            | TgEventData::DisableProxy
+            | TgEventData::RemoveProxy(_)
+            | TgEventData::PingProxy(_)
+            | TgEventData::GetOption(_)
+            | TgEventData::SetOption(_)
+            | TgEventData::SetLogStream(_)
+            | TgEventData::SetLogVerbosityLevel(_)
+            | TgEventData::SetLogTagVerbosityLevel(_)
+            | TgEventData::GetLogTags
+            | TgEventData::OpenChat(_)
+            | TgEventData::CloseChat(_) => MethodClass::Local,
+            TgEventData::DownloadFile(_) => MethodClass::Files,
+            TgEventData::SendMessage(_)
+            | TgEventData::EditMessageText(_)
+            | TgEventData::DeleteMessages(_)
+            | TgEventData::ForwardMessages(_)
+            | TgEventData::AddMessageReaction(_)
+            | TgEventData::RemoveMessageReaction(_)
+            | TgEventData::StopPoll(_)
+            | TgEventData::SetPollAnswer(_)
+            | TgEventData::AddChatMember(_)
+            | TgEventData::BanChatMember(_)
+            | TgEventData::SetChatMemberStatus(_)
+            | TgEventData::CreateChatInviteLink(_)
+            | TgEventData::RevokeChatInviteLink(_)
+            | TgEventData::ProcessChatJoinRequest(_)
+            | TgEventData::SetChatTitle(_)
+            | TgEventData::SetChatDescription(_)
+            | TgEventData::SetChatPhoto(_)
+            | TgEventData::SetChatMessageTtl(_)
+            | TgEventData::ImportContacts(_)
+            | TgEventData::AddContact(_)
+            | TgEventData::RemoveContacts(_)
+            | TgEventData::CreatePrivateChat(_)
+            | TgEventData::CreateNewSecretChat(_)
+            | TgEventData::CloseSecretChat(_) => MethodClass::Write,
+            _ => MethodClass::Read,
+        }
+    }
+
+}
+
+/// Requests per second, and how many can go at once after a quiet period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub rate: f64,
+    pub burst: f64,
+}
+
+/// Limits of the method classes that have any.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub read: Limit,
+    pub write: Limit,
+    pub files: Limit,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            read: Limit { rate: 20.0, burst: 40.0 },
+            write: Limit { rate: 3.0, burst: 10.0 },
+            files: Limit { rate: 4.0, burst: 8.0 },
+        }
+    }
+}
+
+impl Limits {
+    /// Parses limits like `read=20/40,write=1`, a missing burst being the rate and missing classes the defaults.
+    pub fn parse(value: &str) -> Option<Limits> {
+        let mut limits = Limits::default();
+        for limit in value.split(',') {
+            let (class, limit) = limit.trim().split_once('=')?;
+            let (rate, burst) = match limit.split_once('/') {
+                Some((rate, burst)) => (rate.parse::<f64>().ok()?, burst.parse::<f64>().ok()?),
+                None => {
+                    let rate = limit.parse::<f64>().ok()?;
+                    (rate, rate.max(1.0))
+                }
+            };
+            if !(rate > 0.0 && burst >= 1.0) {
+                return None;
+            }
+            let limit = Limit { rate, burst };
+            match class {
+                "read" => limits.read = limit,
+                "write" => limits.write = limit,
+                "files" => limits.files = limit,
+                _ => return None,
+            }
+        }
+        Some(limits)
+    }
+}
+
+struct Bucket {
+    limit: Limit,
+    tokens: f64,
+    refilled: Instant,
+    /// Interactive requests waiting for a token, background ones hold back while there are any
+    interactive_waiting: usize,
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let refilled = now.duration_since(self.refilled).as_secs_f64() * self.limit.rate;
+        self.tokens = (self.tokens + refilled).min(self.limit.burst);
+        self.refilled = now;
+    }
+}
+
+/// Token buckets in front of `Client::send`, one per method class.
+#[derive(Clone)]
+pub struct Scheduler {
+    buckets: Arc<Mutex<HashMap<MethodClass, Bucket>>>,
+}
+
+/// Counts an interactive request as waiting until it's dropped, be it sent or cancelled.
+struct Waiting<'a> {
+    scheduler: &'a Scheduler,
+    class: MethodClass,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.scheduler.with_bucket(self.class, |bucket| bucket.interactive_waiting -= 1);
+    }
+}
+
+impl Scheduler {
+    pub fn new(limits: Limits) -> Scheduler {
+        let now = Instant::now();
+        let bucket = |(class, limit): (MethodClass, Limit)| {
+            (
+                class,
+                Bucket {
+                    limit,
+                    tokens: limit.burst,
+                    refilled: now,
+                    interactive_waiting: 0,
+                },
+            )
+        };
+
+        Scheduler {
+            buckets: Arc::new(Mutex::new(
+                [
+                    (MethodClass::Read, limits.read),
+                    (MethodClass::Write, limits.write),
+                    (MethodClass::Files, limits.files),
+                ]
+                .into_iter()
+                .map(bucket)
+                .collect(),
+            )),
+        }
+    }
+
+    fn with_bucket<T>(&self, class: MethodClass, f: impl FnOnce(&mut Bucket) -> T) -> T {
+        let mut buckets = self.buckets.lock().expect("Can't lock mutex of scheduler buckets");
+        f(buckets.get_mut(&class).expect("No bucket for method class"))
+    }
+
+    /// Waits until a request of the class may be sent.
+    pub async fn acquire(&self, class: MethodClass, priority: Priority) {
+        if class == MethodClass::Local {
+            return;
+        }
+
+        let _waiting = if priority == Priority::Interactive {
+            self.with_bucket(class, |bucket| bucket.interactive_waiting += 1);
+            Some(Waiting { scheduler: self, class })
+        } else {
+            None
+        };
+
+        loop {
+            let wait = self.with_bucket(class, |bucket| {
+                bucket.refill();
+
+                let yields = priority == Priority::Background && bucket.interactive_waiting > 0;
+                if bucket.tokens >= 1.0 && !yields {
+                    bucket.tokens -= 1.0;
+                    None
+                } else {
+                    // Until the next token, background requests check back a bit later so interactive ones go first
+                    let rate = bucket.limit.rate;
+                    let until_token = ((1.0 - bucket.tokens).max(0.0) / rate).max(0.001);
+                    let backoff = if priority == Priority::Background { 1.0 / rate } else { 0.0 };
+                    Some(Duration::from_secs_f64(until_token + backoff))
+                }
+            });
+
+            match wait {
+                Some(wait) => tokio::time::delay_for(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits() {
+        let limits = Limits::parse("write=1, files=2/5").unwrap();
+        assert_eq!(limits.read, Limits::default().read);
+        assert_eq!(limits.write, Limit { rate: 1.0, burst: 1.0 });
+        assert_eq!(limits.files, Limit { rate: 2.0, burst: 5.0 });
+
+        assert_eq!(Limits::parse("send=1"), None);
+        assert_eq!(Limits::parse("read=0"), None);
+        assert_eq!(Limits::parse("read=5/0.5"), None);
+        assert_eq!(Limits::parse("read"), None);
+    }
+
+    #[tokio::test]
+    async fn interactive_goes_before_background() {
+        let scheduler = Scheduler::new(Limits {
+            write: Limit { rate: 10.0, burst: 1.0 },
+            ..Limits::default()
+        });
+        scheduler.acquire(MethodClass::Write, Priority::Interactive).await;
+
+        // The background request starts waiting first, but the interactive one still gets the next token
+        let order = Mutex::new(Vec::new());
+        let acquire = |priority| {
+            let (scheduler, order) = (&scheduler, &order);
+            async move {
+                scheduler.acquire(MethodClass::Write, priority).await;
+                order.lock().unwrap().push(priority);
+            }
+        };
+        tokio::join!(acquire(Priority::Background), acquire(Priority::Interactive));
+
+        assert_eq!(*order.lock().unwrap(), vec![Priority::Interactive, Priority::Background]);
+    }
+}
@@ -0,0 +1,1112 @@
+use crate::cli;
+use crate::telegram::{
+    api::{
+        Chat, ChatAction, ChatType, ConnectionState, Error, Message, MessageContent, MessageSender, Poll, PollType,
+        ReactionType, SecretChat, SecretChatState, TgEventData, User, UserStatus,
+    },
+    manager::Manager,
+};
+use chrono::prelude::DateTime;
+use chrono::Local;
+use colored::Colorize;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use std::time::{Duration, UNIX_EPOCH};
+
+pub trait PrettyPrint {
+    fn pretty_format(&self) -> String;
+}
+
+impl PrettyPrint for DateTime<Local> {
+    fn pretty_format(&self) -> String {
+        let now: DateTime<Local> = Local::now();
+
+        if self.date() == now.date() {
+            self.format("%H:%M ").to_string()
+        } else {
+            self.format("%m.%d ").to_string()
+        }
+    }
+}
+
+fn last_seen(time: DateTime<Local>, now: DateTime<Local>) -> String {
+    let days = (now.date() - time.date()).num_days();
+
+    match days {
+        0 => time.format("%H:%M").to_string(),
+        1 => time.format("yesterday %H:%M").to_string(),
+        2..=6 => format!("{} days ago", days),
+        _ => time.format("%m.%d").to_string(),
+    }
+}
+
+impl PrettyPrint for UserStatus {
+    fn pretty_format(&self) -> String {
+        let now = Local::now();
+
+        match self.at(now.timestamp() as i32) {
+            UserStatus::UserStatusEmpty => "".to_owned(),
+            UserStatus::UserStatusLastMonth => "(last seen within a month) ".to_owned(),
+            UserStatus::UserStatusLastWeek => "(last seen within a week) ".to_owned(),
+            UserStatus::UserStatusOnline { .. } => "(online) ".to_owned(),
+            UserStatus::UserStatusOffline { was_online } => {
+                format!("(last seen {}) ", last_seen(datetime(was_online), now))
+            }
+            UserStatus::UserStatusRecently => "(last seen recently) ".to_owned(),
+        }
+    }
+}
+
+impl PrettyPrint for Poll {
+    /// Question followed by a bar per option, e.g. `██████░░░░░░  50% Yes ✓`.
+    fn pretty_format(&self) -> String {
+        let correct_option_id = match &self.poll_type {
+            PollType::PollTypeQuiz { correct_option_id, .. } => *correct_option_id,
+            PollType::PollTypeRegular { .. } => -1,
+        };
+
+        let kind = match (&self.poll_type, self.is_closed) {
+            (_, true) => "final results",
+            (PollType::PollTypeQuiz { .. }, false) => "quiz",
+            (PollType::PollTypeRegular { .. }, false) => "poll",
+        };
+
+        let mut lines = vec![format!(
+            "{} {} {}",
+            "📊".bold(),
+            self.question.bold(),
+            format!("({}, {} votes)", kind, self.total_voter_count).bright_black()
+        )];
+
+        for (index, option) in self.options.iter().enumerate() {
+            let filled = (option.vote_percentage.clamp(0, 100) / 5) as usize;
+            let bar = format!("{}{}", "█".repeat(filled), "░".repeat(20 - filled));
+
+            let mut marks = String::new();
+            if option.is_chosen {
+                marks.push_str(" ✓");
+            }
+            if index as i32 == correct_option_id {
+                marks.push_str(" (correct)");
+            }
+
+            lines.push(format!(
+                "  {} {:>3}% {}{}{}",
+                bar.blue(),
+                option.vote_percentage,
+                format!("{}. ", index + 1).bright_black(),
+                option.text,
+                marks.bold().green()
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl PrettyPrint for MessageContent {
+    fn pretty_format(&self) -> String {
+        match self {
+            MessageContent::MessageText(body) => body.text.text.clone(),
+            MessageContent::MessageDocument(body) => {
+                format!("{} {}", format!("[{}]", body.document.file_name).underline(), body.caption.text)
+            }
+            MessageContent::MessagePhoto(body) => format!("{} {}", "[photo]".underline(), body.caption.text),
+            MessageContent::MessageVideo(body) => format!("{} {}", "[video]".underline(), body.caption.text),
+            MessageContent::MessageAudio(body) => {
+                format!("{} {}", format!("[{}]", body.audio.file_name).underline(), body.caption.text)
+            }
+            MessageContent::MessageVoiceNote(body) => format!("{} {}", "[voice]".underline(), body.caption.text),
+            MessageContent::MessagePoll(body) => body.poll.pretty_format(),
+            it => format!("{:?}", it).underline().to_string(),
+        }
+    }
+}
+
+impl PrettyPrint for ConnectionState {
+    fn pretty_format(&self) -> String {
+        match self {
+            ConnectionState::ConnectionStateWaitingForNetwork => "Waiting for network…",
+            ConnectionState::ConnectionStateConnectingToProxy => "Connecting to proxy…",
+            ConnectionState::ConnectionStateConnecting => "Connecting…",
+            ConnectionState::ConnectionStateUpdating => "Updating…",
+            ConnectionState::ConnectionStateReady => "",
+        }
+        .to_owned()
+    }
+}
+
+/// Bottom line of a view telling what's shown may be out of date, nothing once connected.
+/// Shows when Telegram isn't reachable, and why the view couldn't load something last time it tried.
+fn status_bar(connection_state: &ConnectionState, error: &Option<Error>) {
+    if *connection_state != ConnectionState::ConnectionStateReady {
+        println!("{}", format!("⚠ {}", connection_state.pretty_format()).bold().yellow());
+    }
+    if let Some(error) = error {
+        println!("{}", format!("⚠ {}", error.message).bold().red());
+    }
+}
+
+impl PrettyPrint for ReactionType {
+    fn pretty_format(&self) -> String {
+        match self {
+            ReactionType::ReactionTypeEmoji { emoji } => emoji.clone(),
+            ReactionType::ReactionTypeCustomEmoji { .. } => "★".to_owned(),
+        }
+    }
+}
+
+/// Self-destruct timer in the largest unit that fits, e.g. `30s`, `5m` or `1w`.
+pub fn ttl(seconds: i32) -> String {
+    match seconds {
+        s if s >= 7 * 24 * 60 * 60 && s % (7 * 24 * 60 * 60) == 0 => format!("{}w", s / (7 * 24 * 60 * 60)),
+        s if s >= 24 * 60 * 60 && s % (24 * 60 * 60) == 0 => format!("{}d", s / (24 * 60 * 60)),
+        s if s >= 60 * 60 && s % (60 * 60) == 0 => format!("{}h", s / (60 * 60)),
+        s if s >= 60 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Colors of the key picture, by the two bits of a square.
+fn key_picture(bytes: &[u8]) -> Vec<Vec<u8>> {
+    // 16 bytes make an 8x8 picture, the 36 of newer layers a 12x12 one
+    let side = if bytes.len() > 16 { 12 } else { 8 };
+
+    (0..side)
+        .map(|row| {
+            (0..side)
+                .map(|column| {
+                    let bit = (row * side + column) * 2;
+                    bytes.get(bit / 8).map(|byte| (byte >> (bit % 8)) & 0b11).unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The key hash drawn like Telegram's apps draw it, so it can be compared with the other side: every byte at two
+/// bits per square, and for newer layers also the hex of the first 32 bytes.
+pub fn key_fingerprint(secret_chat: &SecretChat) -> String {
+    const COLORS: [(u8, u8, u8); 4] = [(255, 255, 255), (213, 230, 243), (45, 87, 117), (47, 153, 201)];
+
+    let bytes = secret_chat.key_hash_bytes();
+    let mut lines: Vec<String> = key_picture(&bytes)
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|color| {
+                    let (r, g, b) = COLORS[*color as usize];
+                    "  ".on_truecolor(r, g, b).to_string()
+                })
+                .collect()
+        })
+        .collect();
+
+    if bytes.len() > 16 {
+        lines.push("".to_owned());
+        for row in bytes.iter().take(32).collect::<Vec<_>>().chunks(8) {
+            let hex = row
+                .chunks(4)
+                .map(|group| group.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+                .join("  ");
+            lines.push(hex);
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// State of a secret chat worth mentioning next to its title, nothing once it's ready.
+fn secret_state(secret_chat: &SecretChat, users: &HashMap<i64, User>) -> String {
+    match (&secret_chat.state, users.get(&secret_chat.user_id)) {
+        (SecretChatState::SecretChatStatePending, Some(user)) if secret_chat.is_outbound => {
+            format!("(waiting for {} to come online) ", full_name(user))
+        }
+        (SecretChatState::SecretChatStatePending, _) => "(pending) ".to_owned(),
+        (SecretChatState::SecretChatStateReady, _) => "".to_owned(),
+        (SecretChatState::SecretChatStateClosed, _) => "(closed) ".to_owned(),
+    }
+}
+
+pub fn datetime(unixtime: i32) -> DateTime<Local> {
+    let epoch = UNIX_EPOCH + Duration::from_secs(unixtime.try_into().expect("Can't convert unixtime to Duration"));
+    DateTime::<Local>::from(epoch)
+}
+
+pub fn full_name(user: &User) -> String {
+    format!("{} {}", user.first_name, user.last_name).trim().to_owned()
+}
+
+/// Applies `UpdateUser`/`UpdateUserStatus` to a view's users, returning whether a known user changed.
+/// Statuses are kept by the manager, see `user_status`.
+fn apply_user_update(users: &mut HashMap<i64, User>, update: &TgEventData) -> bool {
+    match update {
+        TgEventData::UpdateUser(update) if users.contains_key(&update.user.id) => {
+            users.insert(update.user.id, update.user.clone());
+            true
+        }
+        TgEventData::UpdateUserStatus(update) => users.contains_key(&update.user_id),
+        _ => false,
+    }
+}
+
+/// Online status or last seen time of the user, as the manager last heard of it.
+fn user_status(manager: &Manager, user: &User) -> String {
+    manager
+        .user_status(user.id)
+        .unwrap_or_else(|| user.status.clone())
+        .pretty_format()
+}
+
+fn private_user_id(chat: &Chat) -> Option<i64> {
+    match chat.chat_type {
+        ChatType::ChatTypePrivate { user_id } | ChatType::ChatTypeSecret { user_id, .. } => Some(user_id),
+        _ => None,
+    }
+}
+
+fn secret_chat_id(chat: &Chat) -> Option<i32> {
+    if let ChatType::ChatTypeSecret { secret_chat_id, .. } = chat.chat_type {
+        Some(secret_chat_id)
+    } else {
+        None
+    }
+}
+
+/// Fetches secret chats that aren't known to the view yet, from the manager's cache if possible.
+/// Those that can't be fetched are left out and asked for again next time, the error says why.
+async fn fetch_secret_chats(
+    manager: &Manager,
+    secret_chats: &mut HashMap<i32, SecretChat>,
+    ids: Vec<i32>,
+) -> Result<(), Error> {
+    let mut result = Ok(());
+    for id in ids {
+        if let Entry::Vacant(entry) = secret_chats.entry(id) {
+            let secret_chat = match manager.secret_chat(id) {
+                Some(secret_chat) => Ok(secret_chat),
+                None => manager.get_secret_chat(id).await,
+            };
+            match secret_chat {
+                Ok(secret_chat) => {
+                    entry.insert(secret_chat);
+                }
+                Err(error) => result = Err(error),
+            }
+        }
+    }
+    result
+}
+
+fn sender_user_id(message: &Message) -> Option<i64> {
+    if let MessageSender::MessageSenderUser(user) = &message.sender_id {
+        Some(user.user_id)
+    } else {
+        None
+    }
+}
+
+/// Read state of the last message: ✓ when our message is sent, ✓✓ once the other side read it.
+fn read_state(chat: &Chat) -> String {
+    if let Some(message) = chat.last_message.as_ref().filter(|message| message.is_outgoing) {
+        if message.id <= chat.last_read_outbox_message_id {
+            "✓✓ ".blue().to_string()
+        } else {
+            "✓ ".bright_black().to_string()
+        }
+    } else if chat.is_marked_as_unread && chat.unread_count == 0 {
+        "● ".magenta().to_string()
+    } else {
+        "".to_string()
+    }
+}
+
+/// Chat list, kept in sync with chat updates.
+pub struct ChatListView {
+    chats: Vec<Chat>,
+    users: HashMap<i64, User>,
+    secret_chats: HashMap<i32, SecretChat>,
+    actions: ChatActions,
+    connection_state: ConnectionState,
+    error: Option<Error>,
+}
+
+impl ChatListView {
+    pub fn new(chats: Vec<Chat>) -> ChatListView {
+        ChatListView {
+            chats,
+            users: HashMap::new(),
+            secret_chats: HashMap::new(),
+            actions: ChatActions::default(),
+            connection_state: ConnectionState::ConnectionStateReady,
+            error: None,
+        }
+    }
+
+    fn chat_mut(&mut self, chat_id: i64) -> Option<&mut Chat> {
+        self.chats.iter_mut().find(|chat| chat.id == chat_id)
+    }
+
+    /// Applies an update to the view, returning whether anything visible changed.
+    pub fn apply(&mut self, update: &TgEventData) -> bool {
+        if self.actions.apply(update) || apply_user_update(&mut self.users, update) {
+            return true;
+        }
+
+        match update {
+            TgEventData::UpdateChatLastMessage(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.last_message = update.last_message.clone();
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateChatReadInbox(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.last_read_inbox_message_id = update.last_read_inbox_message_id;
+                    chat.unread_count = update.unread_count;
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateChatReadOutbox(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.last_read_outbox_message_id = update.last_read_outbox_message_id;
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateChatIsMarkedAsUnread(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.is_marked_as_unread = update.is_marked_as_unread;
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateChatUnreadMentionCount(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.unread_mention_count = update.unread_mention_count;
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateChatUnreadReactionCount(update) => match self.chat_mut(update.chat_id) {
+                Some(chat) => {
+                    chat.unread_reaction_count = update.unread_reaction_count;
+                    true
+                }
+                None => false,
+            },
+            TgEventData::UpdateSecretChat(update) => self
+                .secret_chats
+                .insert(update.secret_chat.id, update.secret_chat.clone())
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Fetches senders of last messages and secret chats that aren't known to the view yet.
+    pub async fn load(&mut self, manager: &Manager) {
+        let ids = self
+            .chats
+            .iter()
+            .flat_map(|chat| {
+                let sender = chat.last_message.as_ref().and_then(sender_user_id);
+                sender.into_iter().chain(private_user_id(chat))
+            })
+            .chain(self.actions.user_ids())
+            .collect();
+        let users = fetch_users(manager, &mut self.users, ids).await;
+
+        let ids = self.chats.iter().flat_map(secret_chat_id).collect();
+        let secret_chats = fetch_secret_chats(manager, &mut self.secret_chats, ids).await;
+        self.error = users.and(secret_chats).err();
+    }
+
+    pub fn render(&self, manager: &Manager) {
+        print!("\x1B[2J\x1B[H");
+
+        for chat in self.chats.iter().rev() {
+            let secret_chat = secret_chat_id(chat).and_then(|id| self.secret_chats.get(&id));
+            let title = match secret_chat {
+                Some(_) => format!("🔒 {} ", chat.title),
+                None => format!("{} ", chat.title),
+            };
+            let secret_state = secret_chat
+                .map(|secret_chat| secret_state(secret_chat, &self.users))
+                .unwrap_or_default();
+
+            let unread_count = if chat.unread_count > 0 {
+                format!("({}+) ", chat.unread_count)
+            } else {
+                "".to_string()
+            };
+
+            let unread_mentions = if chat.unread_mention_count > 0 {
+                format!("@{} ", chat.unread_mention_count)
+            } else {
+                "".to_string()
+            };
+
+            let unread_reactions = if chat.unread_reaction_count > 0 {
+                format!("♥{} ", chat.unread_reaction_count)
+            } else {
+                "".to_string()
+            };
+
+            let sender = chat.last_message.as_ref().and_then(sender_user_id).and_then(|id| self.users.get(&id));
+
+            let user = match (&chat.chat_type, sender) {
+                (ChatType::ChatTypeBasicGroup { .. } | ChatType::ChatTypeSupergroup { .. }, Some(user)) => {
+                    format!("{} ", full_name(user))
+                }
+                _ => "".to_string(),
+            };
+
+            let user_status = match private_user_id(chat).and_then(|id| self.users.get(&id)) {
+                Some(user) => user_status(manager, user),
+                None => "".to_string(),
+            };
+
+            println!(
+                "{}{}{}{}{}{}{}{}",
+                title.bold().blue(),
+                secret_state.bright_black(),
+                user.bright_black(),
+                read_state(chat).bold(),
+                user_status.bold().blue(),
+                unread_count.bold().magenta(),
+                unread_mentions.bold().magenta(),
+                unread_reactions.bold().magenta()
+            );
+
+            let body = match self.actions.describe(chat.id, &self.users) {
+                Some(action) => action.italic().blue().to_string(),
+                None => chat.last_message.as_ref().map(|message| message.content.pretty_format()).unwrap_or_default(),
+            };
+            let datetime = chat
+                .last_message
+                .as_ref()
+                .map(|message| datetime(message.date).pretty_format())
+                .unwrap_or_default();
+
+            println!("{}{}\n", datetime.bold().bright_black(), body);
+        }
+
+        status_bar(&self.connection_state, &self.error);
+    }
+}
+
+fn action_text(action: &ChatAction) -> &'static str {
+    match action {
+        ChatAction::ChatActionTyping => "is typing…",
+        ChatAction::ChatActionRecordingVideo => "is recording a video…",
+        ChatAction::ChatActionUploadingVideo { .. } => "is sending a video…",
+        ChatAction::ChatActionRecordingVoiceNote => "is recording a voice message…",
+        ChatAction::ChatActionUploadingVoiceNote { .. } => "is sending a voice message…",
+        ChatAction::ChatActionUploadingPhoto { .. } => "is sending a photo…",
+        ChatAction::ChatActionUploadingDocument { .. } => "is sending a file…",
+        ChatAction::ChatActionChoosingSticker => "is choosing a sticker…",
+        ChatAction::ChatActionChoosingLocation => "is choosing a location…",
+        ChatAction::ChatActionChoosingContact => "is choosing a contact…",
+        ChatAction::ChatActionStartPlayingGame => "is playing a game…",
+        ChatAction::ChatActionRecordingVideoNote => "is recording a video message…",
+        ChatAction::ChatActionUploadingVideoNote { .. } => "is sending a video message…",
+        ChatAction::ChatActionWatchingAnimations { .. } => "is watching an animation…",
+        ChatAction::ChatActionCancel => "",
+    }
+}
+
+/// What users are currently doing in each chat, from `UpdateChatAction`.
+/// TDLib sends `ChatActionCancel` itself once an action times out.
+#[derive(Default)]
+struct ChatActions {
+    actions: HashMap<i64, Vec<(i64, ChatAction)>>,
+}
+
+impl ChatActions {
+    fn apply(&mut self, update: &TgEventData) -> bool {
+        let update = match update {
+            TgEventData::UpdateChatAction(update) if update.message_thread_id == 0 => update,
+            _ => return false,
+        };
+
+        let user_id = match &update.sender_id {
+            MessageSender::MessageSenderUser(user) => user.user_id,
+            MessageSender::MessageSenderChat(_) => return false,
+        };
+
+        let actions = self.actions.entry(update.chat_id).or_default();
+        actions.retain(|(id, _)| *id != user_id);
+
+        if update.action != ChatAction::ChatActionCancel {
+            actions.push((user_id, update.action.clone()));
+        }
+
+        true
+    }
+
+    fn user_ids(&self) -> Vec<i64> {
+        self.actions.values().flatten().map(|(id, _)| *id).collect()
+    }
+
+    /// "Alice is typing…" for the first user acting in the chat, with a count of the others.
+    fn describe(&self, chat_id: i64, users: &HashMap<i64, User>) -> Option<String> {
+        let actions = self.actions.get(&chat_id)?;
+        let (user_id, action) = actions.first()?;
+
+        let name = users.get(user_id).map(full_name).unwrap_or_else(|| "Someone".to_owned());
+        let others = if actions.len() > 1 {
+            format!(" (+{})", actions.len() - 1)
+        } else {
+            "".to_owned()
+        };
+
+        Some(format!("{} {}{}", name, action_text(action), others))
+    }
+}
+
+/// Fetches users that aren't known to the view yet. Those that can't be fetched are left out and asked for again
+/// next time, the error says why.
+async fn fetch_users(manager: &Manager, users: &mut HashMap<i64, User>, ids: Vec<i64>) -> Result<(), Error> {
+    let mut result = Ok(());
+    for id in ids {
+        if let Entry::Vacant(entry) = users.entry(id) {
+            match manager.get_user(id).await {
+                Ok(user) => {
+                    entry.insert(user);
+                }
+                Err(error) => result = Err(error),
+            }
+        }
+    }
+    result
+}
+
+pub async fn chat_list(manager: &Manager) {
+    let mut updates = manager.updates();
+    let chat_ids = manager.get_chats(40).await.unwrap_or_else(|error| cli::exit_with_error(error)).chat_ids;
+
+    let chats = stream::iter(chat_ids)
+        .then(|id| manager.get_chat(id))
+        .try_collect::<Vec<Chat>>()
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    let mut connection_state = manager.connection_state();
+    let mut view = ChatListView::new(chats);
+    view.connection_state = connection_state.borrow().clone();
+    view.load(manager).await;
+    view.render(manager);
+
+    // Re-render now and then, so online statuses expire and "last seen" stays accurate without updates
+    let mut refresh = tokio::time::interval(Duration::from_secs(30));
+
+    loop {
+        tokio::select! {
+            Some(state) = connection_state.recv() => {
+                view.connection_state = state;
+                view.render(manager);
+            }
+            update = updates.next() => match update {
+                Some(update) => {
+                    if view.apply(&update) {
+                        view.load(manager).await;
+                        view.render(manager);
+                    }
+                }
+                None => break,
+            },
+            _ = refresh.tick() => view.render(manager),
+        }
+    }
+}
+
+/// Prints found messages the way the chat list does: chat title and sender, then date and body.
+pub async fn search_results(manager: &Manager, messages: &[Message]) {
+    let mut chats: HashMap<i64, Chat> = HashMap::new();
+    let mut users: HashMap<i64, User> = HashMap::new();
+
+    for message in messages {
+        if let Entry::Vacant(entry) = chats.entry(message.chat_id) {
+            entry.insert(manager.get_chat(message.chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error)));
+        }
+
+        let sender = if let Some(id) = sender_user_id(message) {
+            if let Err(error) = fetch_users(manager, &mut users, vec![id]).await {
+                eprintln!("Can't get sender of message {}: {}", message.id, error.message);
+            }
+            users.get(&id).map(|user| format!("{} ", full_name(user)))
+        } else {
+            None
+        };
+
+        let chat = &chats[&message.chat_id];
+
+        println!(
+            "{}{}{}",
+            format!("{} ", chat.title).bold().blue(),
+            sender.unwrap_or_default().bright_black(),
+            format!("#{}", message.id).bright_black()
+        );
+        println!(
+            "{}{}\n",
+            datetime(message.date).pretty_format().bold().bright_black(),
+            message.content.pretty_format()
+        );
+    }
+}
+
+/// First line of the message, shortened to fit a quote.
+fn preview(message: &Message) -> String {
+    let body = message.content.pretty_format();
+    let line = body.lines().next().unwrap_or_default();
+
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_owned()
+    }
+}
+
+/// Conversation with a single chat or one of its message threads, kept in sync with message updates.
+pub struct ChatView {
+    chat: Chat,
+    message_thread_id: i64,
+    messages: BTreeMap<i64, Message>,
+    replied: HashMap<i64, Message>,
+    /// Messages replied to that are deleted or not accessible, so they aren't asked for again
+    unresolved: HashSet<i64>,
+    users: HashMap<i64, User>,
+    secret_chat: Option<SecretChat>,
+    actions: ChatActions,
+    connection_state: ConnectionState,
+    error: Option<Error>,
+}
+
+impl ChatView {
+    /// A view of the whole chat when `message_thread_id` is 0, otherwise of just that thread.
+    pub fn new(chat: Chat, message_thread_id: i64, history: Vec<Message>) -> ChatView {
+        ChatView {
+            chat,
+            message_thread_id,
+            messages: history.into_iter().map(|message| (message.id, message)).collect(),
+            replied: HashMap::new(),
+            unresolved: HashSet::new(),
+            users: HashMap::new(),
+            secret_chat: None,
+            actions: ChatActions::default(),
+            connection_state: ConnectionState::ConnectionStateReady,
+            error: None,
+        }
+    }
+
+    fn shows(&self, message: &Message) -> bool {
+        message.chat_id == self.chat.id
+            && (self.message_thread_id == 0 || message.message_thread_id == self.message_thread_id)
+    }
+
+    /// Applies an update to the view, returning whether anything visible changed.
+    pub fn apply(&mut self, update: &TgEventData) -> bool {
+        match update {
+            TgEventData::UpdateChatAction(action) if action.chat_id == self.chat.id => self.actions.apply(update),
+            TgEventData::UpdateUser(_) | TgEventData::UpdateUserStatus(_) => apply_user_update(&mut self.users, update),
+            TgEventData::UpdateNewMessage(update) if self.shows(&update.message) => {
+                self.messages.insert(update.message.id, update.message.clone());
+                true
+            }
+            TgEventData::UpdateMessageSendSucceeded(update) if self.shows(&update.message) => {
+                self.messages.remove(&update.old_message_id);
+                self.messages.insert(update.message.id, update.message.clone());
+                true
+            }
+            TgEventData::UpdateMessageContent(update) if update.chat_id == self.chat.id => {
+                if let Some(message) = self.messages.get_mut(&update.message_id) {
+                    message.content = update.new_content.clone();
+                    true
+                } else {
+                    false
+                }
+            }
+            TgEventData::UpdateMessageEdited(update) if update.chat_id == self.chat.id => {
+                if let Some(message) = self.messages.get_mut(&update.message_id) {
+                    message.edit_date = update.edit_date;
+                    true
+                } else {
+                    false
+                }
+            }
+            TgEventData::UpdatePoll(update) => {
+                let mut changed = false;
+                for message in self.messages.values_mut() {
+                    if let MessageContent::MessagePoll(body) = &mut message.content {
+                        if body.poll.id == update.poll.id {
+                            body.poll = update.poll.clone();
+                            changed = true;
+                        }
+                    }
+                }
+                changed
+            }
+            TgEventData::UpdateMessageInteractionInfo(update) if update.chat_id == self.chat.id => {
+                if let Some(message) = self.messages.get_mut(&update.message_id) {
+                    message.interaction_info = update.interaction_info.clone();
+                    true
+                } else {
+                    false
+                }
+            }
+            TgEventData::UpdateChatReadInbox(update) if update.chat_id == self.chat.id => {
+                self.chat.last_read_inbox_message_id = update.last_read_inbox_message_id;
+                false
+            }
+            TgEventData::UpdateChatReadOutbox(update) if update.chat_id == self.chat.id => {
+                self.chat.last_read_outbox_message_id = update.last_read_outbox_message_id;
+                true
+            }
+            TgEventData::UpdateSecretChat(update) if secret_chat_id(&self.chat) == Some(update.secret_chat.id) => {
+                self.secret_chat = Some(update.secret_chat.clone());
+                true
+            }
+            TgEventData::UpdateChatMessageTtl(update) if update.chat_id == self.chat.id => {
+                self.chat.message_ttl = update.message_ttl;
+                true
+            }
+            TgEventData::UpdateDeleteMessages(update) if update.chat_id == self.chat.id && update.is_permanent => {
+                let removed = update
+                    .message_ids
+                    .iter()
+                    .filter(|id| self.messages.remove(id).is_some())
+                    .count();
+                removed > 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Counts down self-destruct timers that are running, returning whether there were any.
+    /// TDLib deletes the messages once they run out.
+    pub fn tick(&mut self, elapsed: f64) -> bool {
+        let mut ticking = false;
+        for message in self.messages.values_mut() {
+            if message.ttl_expires_in > 0.0 {
+                message.ttl_expires_in = (message.ttl_expires_in - elapsed).max(0.0);
+                ticking = true;
+            }
+        }
+        ticking
+    }
+
+    /// Incoming messages on screen that TDLib hasn't seen us read yet.
+    pub fn unread_ids(&self) -> Vec<i64> {
+        self.messages
+            .values()
+            .filter(|message| !message.is_outgoing && message.id > self.chat.last_read_inbox_message_id)
+            .map(|message| message.id)
+            .collect()
+    }
+
+    /// Fetches replied-to messages and senders that aren't known to the view yet.
+    pub async fn load(&mut self, manager: &Manager) {
+        let missing = self
+            .messages
+            .values()
+            .filter(|message| message.reply_to_message_id != 0)
+            .filter(|message| {
+                !self.messages.contains_key(&message.reply_to_message_id)
+                    && !self.replied.contains_key(&message.reply_to_message_id)
+                    && !self.unresolved.contains(&message.reply_to_message_id)
+            })
+            .map(|message| (message.chat_id, message.id, message.reply_to_message_id))
+            .collect::<Vec<(i64, i64, i64)>>();
+
+        for (chat_id, message_id, reply_to_message_id) in missing {
+            match manager.get_replied_message(chat_id, message_id).await {
+                Some(replied) => {
+                    self.replied.insert(replied.id, replied);
+                }
+                None => {
+                    self.unresolved.insert(reply_to_message_id);
+                }
+            }
+        }
+
+        let ids = self
+            .messages
+            .values()
+            .chain(self.replied.values())
+            .flat_map(sender_user_id)
+            .chain(private_user_id(&self.chat))
+            .chain(self.actions.user_ids())
+            .collect();
+        let users = fetch_users(manager, &mut self.users, ids).await;
+
+        let mut secret_chats = HashMap::new();
+        let ids = secret_chat_id(&self.chat).filter(|_| self.secret_chat.is_none()).into_iter().collect();
+        let secret_chat = fetch_secret_chats(manager, &mut secret_chats, ids).await;
+        if let Some(secret_chat) = secret_chats.into_values().next() {
+            self.secret_chat = Some(secret_chat);
+        }
+        self.error = users.and(secret_chat).err();
+    }
+
+    fn sender_name(&self, message: &Message) -> String {
+        match sender_user_id(message).and_then(|id| self.users.get(&id)) {
+            Some(user) => full_name(user),
+            None => self.chat.title.clone(),
+        }
+    }
+
+    pub fn render(&self, manager: &Manager) {
+        print!("\x1B[2J\x1B[H");
+
+        let status = match private_user_id(&self.chat).and_then(|id| self.users.get(&id)) {
+            Some(user) => user_status(manager, user),
+            None => "".to_string(),
+        };
+        let thread = if self.message_thread_id != 0 { "(thread) " } else { "" };
+        let title = match &self.secret_chat {
+            Some(_) => format!("🔒 {}", self.chat.title),
+            None => self.chat.title.clone(),
+        };
+        let secret_state = self
+            .secret_chat
+            .as_ref()
+            .map(|secret_chat| secret_state(secret_chat, &self.users))
+            .unwrap_or_default();
+        let timer = if self.chat.message_ttl > 0 {
+            format!("(⏱ {}) ", ttl(self.chat.message_ttl))
+        } else {
+            "".to_string()
+        };
+        println!(
+            "{} {}{}{}{}\n",
+            title.bold().blue(),
+            thread.bright_black(),
+            secret_state.bright_black(),
+            timer.bright_black(),
+            status.bright_black()
+        );
+
+        for message in self.messages.values() {
+            let edited = if message.edit_date > 0 { " (edited)" } else { "" };
+
+            let read = if !message.is_outgoing {
+                "".normal()
+            } else if message.id <= self.chat.last_read_outbox_message_id {
+                " ✓✓".blue()
+            } else {
+                " ✓".bright_black()
+            };
+
+            let replies = match message.interaction_info.as_ref().and_then(|info| info.reply_info.as_ref()) {
+                Some(info) if info.reply_count > 0 => format!(" 💬 {}", info.reply_count),
+                _ => "".to_string(),
+            };
+
+            // The timer only starts once the message is opened, until then show the full duration
+            let timer = if message.ttl_expires_in > 0.0 {
+                format!(" ⏱ {}", ttl(message.ttl_expires_in.ceil() as i32))
+            } else if message.ttl > 0 {
+                format!(" ⏱ {}", ttl(message.ttl))
+            } else {
+                "".to_string()
+            };
+
+            println!(
+                "{}{} {}{}{}{}{}",
+                datetime(message.date).pretty_format().bold().bright_black(),
+                self.sender_name(message).bold(),
+                format!("#{}", message.id).bright_black(),
+                edited.bright_black(),
+                read,
+                replies.bright_black(),
+                timer.red()
+            );
+
+            if message.reply_to_message_id != 0 {
+                let quote = self
+                    .messages
+                    .get(&message.reply_to_message_id)
+                    .or_else(|| self.replied.get(&message.reply_to_message_id))
+                    .map(|replied| format!("┃ {}: {}", self.sender_name(replied), preview(replied)))
+                    .unwrap_or_else(|| "┃ deleted message".to_owned());
+                println!("{}", quote.bright_black());
+            }
+
+            println!("{}", message.content.pretty_format());
+
+            let reactions = message
+                .interaction_info
+                .as_ref()
+                .map(|info| {
+                    info.reactions
+                        .iter()
+                        .map(|reaction| {
+                            let text = format!("{} {}", reaction.reaction_type.pretty_format(), reaction.total_count);
+                            if reaction.is_chosen {
+                                text.bold().blue().to_string()
+                            } else {
+                                text.bright_black().to_string()
+                            }
+                        })
+                        .collect::<Vec<String>>()
+                        .join("  ")
+                })
+                .unwrap_or_default();
+            println!("{}\n", reactions);
+        }
+
+        if let Some(action) = self.actions.describe(self.chat.id, &self.users) {
+            println!("{}", action.italic().blue());
+        }
+
+        status_bar(&self.connection_state, &self.error);
+    }
+}
+
+/// Renders the view and keeps it up to date until interrupted, marking what's shown as read.
+async fn follow(manager: &Manager, mut view: ChatView, mut updates: UnboundedReceiver<TgEventData>) {
+    let chat_id = view.chat.id;
+    let message_thread_id = view.message_thread_id;
+
+    let mut connection_state = manager.connection_state();
+    view.connection_state = connection_state.borrow().clone();
+    view.load(manager).await;
+
+    let opened = manager.open_chat(chat_id).await;
+    let viewed = manager.view_messages(chat_id, message_thread_id, view.unread_ids(), false).await;
+    if let Err(error) = opened.and(viewed) {
+        view.error = Some(error);
+    }
+    view.render(manager);
+
+    let interrupt = tokio::signal::ctrl_c();
+    tokio::pin!(interrupt);
+
+    let mut countdown = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            update = updates.next() => match update {
+                Some(update) => {
+                    if view.apply(&update) {
+                        view.load(manager).await;
+                        view.render(manager);
+
+                        let unread = view.unread_ids();
+                        if !unread.is_empty() {
+                            if let Err(error) = manager.view_messages(chat_id, message_thread_id, unread, false).await {
+                                view.error = Some(error);
+                                view.render(manager);
+                            }
+                        }
+                    }
+                }
+                None => break,
+            },
+            Some(state) = connection_state.recv() => {
+                view.connection_state = state;
+                view.render(manager);
+            }
+            _ = countdown.tick() => {
+                if view.tick(1.0) {
+                    view.render(manager);
+                }
+            }
+            _ = &mut interrupt => break,
+        }
+    }
+
+    if let Err(error) = manager.close_chat(chat_id).await {
+        eprintln!("Can't close chat: {}", error.message);
+    }
+}
+
+pub async fn conversation(manager: &Manager, chat_id: i64) {
+    let updates = manager.updates();
+    let chat = manager.get_chat(chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+
+    // The first page TDLib returns is often just the last message, keep asking until the screen is filled
+    let mut history = Vec::new();
+    while history.len() < 30 {
+        let from_message_id = history.last().map(|message: &Message| message.id).unwrap_or_default();
+        let page = manager
+            .get_chat_history(chat_id, from_message_id, 30)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+        if page.is_empty() {
+            break;
+        }
+        history.extend(page);
+    }
+
+    follow(manager, ChatView::new(chat, 0, history), updates).await;
+}
+
+/// Replies to a message, or comments to a channel post, shown from the thread's root.
+pub async fn thread(manager: &Manager, chat_id: i64, message_id: i64) {
+    let updates = manager.updates();
+    let info = manager
+        .get_message_thread(chat_id, message_id)
+        .await
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+    let chat = manager.get_chat(info.chat_id).await.unwrap_or_else(|error| cli::exit_with_error(error));
+
+    let mut replies = Vec::new();
+    while replies.len() < 30 {
+        let from_message_id = replies.last().map(|message: &Message| message.id).unwrap_or_default();
+        let page = manager
+            .get_message_thread_history(chat_id, message_id, from_message_id, 30)
+            .await
+            .unwrap_or_else(|error| cli::exit_with_error(error));
+        if page.is_empty() {
+            break;
+        }
+        replies.extend(page);
+    }
+
+    let history = info.messages.iter().cloned().chain(replies).collect();
+    follow(manager, ChatView::new(chat, info.message_thread_id, history), updates).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn secret_chat(key_hash: &str, layer: i32) -> SecretChat {
+        serde_json::from_value(json!({
+            "id": 1,
+            "user_id": 2,
+            "state": { "@type": "secretChatStateReady" },
+            "is_outbound": true,
+            "key_hash": key_hash,
+            "layer": layer,
+        }))
+        .expect("Can't build secret chat")
+    }
+
+    #[test]
+    fn draws_all_36_bytes_of_the_key_hash() {
+        let bytes = secret_chat("5AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAADA", 144).key_hash_bytes();
+        assert_eq!(bytes.len(), 36);
+
+        let picture = key_picture(&bytes);
+        assert_eq!(picture.len(), 12);
+        assert!(picture.iter().all(|row| row.len() == 12));
+        assert_eq!(picture[0][..5], [0, 1, 2, 3, 0]);
+        assert_eq!(picture[11][11], 3);
+        assert_eq!(picture.iter().flatten().filter(|color| **color != 0).count(), 4);
+    }
+
+    #[test]
+    fn draws_old_key_hashes_8_by_8() {
+        let picture = key_picture(&secret_chat("GwAAAAAAAAAAAAAAAAAAAA==", 45).key_hash_bytes());
+        assert_eq!(picture.len(), 8);
+        assert_eq!(picture[0][..4], [3, 2, 1, 0]);
+        assert_eq!(picture[7][7], 0);
+    }
+}